@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use trader_config_formatter::{format, parse};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+// Traders, categories per trader and items per category
+const SIZES: [(&str, usize, usize, usize); 2] = [("small", 2, 5, 20), ("large", 40, 20, 50)];
+
+fn parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, traders, categories, items) in SIZES {
+        let config = common::synthetic_config(traders, categories, items);
+        group.throughput(Throughput::Bytes(config.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &config, |b, config| {
+            b.iter(|| parse(config).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn formatting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("format");
+    for (name, traders, categories, items) in SIZES {
+        let config = common::synthetic_config(traders, categories, items);
+        let tokens = parse(&config).unwrap();
+        group.throughput(Throughput::Bytes(config.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &tokens, |b, tokens| {
+            b.iter(|| format(tokens))
+        });
+    }
+    group.finish();
+}
+
+// Many small blocks so most of the time goes into reading tag names rather than item values
+fn tags(c: &mut Criterion) {
+    let config = common::synthetic_config(500, 40, 1);
+    let mut group = c.benchmark_group("tags");
+    group.throughput(Throughput::Bytes(config.len() as u64));
+    group.bench_function("parse", |b| b.iter(|| parse(&config).unwrap()));
+    group.finish();
+}
+
+// One currency line with this many values, without a line break after it
+const LONG_LINE_VALUES: usize = 50_000;
+
+fn long_line(c: &mut Criterion) {
+    let config = common::long_line_config(LONG_LINE_VALUES);
+    let mut group = c.benchmark_group("long_line");
+    group.throughput(Throughput::Bytes(config.len() as u64));
+    group.bench_function("parse", |b| b.iter(|| parse(&config).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, parsing, formatting, tags, long_line);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::items::items;
+use crate::token::*;
+
+// A difference between two versions of a config, items are matched by trader, category and class
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub trader: String,
+    pub category: String,
+    pub class: String,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    // One of amount, buy value, sell value or stock, a missing stock is written as none
+    Changed { field: &'static str, old: String, new: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let context = format!("{} > {} > {}", self.trader, self.category, self.class);
+        match &self.kind {
+            ChangeKind::Added => write!(f, "+ {}", context),
+            ChangeKind::Removed => write!(f, "- {}", context),
+            ChangeKind::Changed { field, old, new } => write!(f, "~ {}: {} {} -> {}", context, field, old, new),
+        }
+    }
+}
+
+// Removed and changed items in the order of the old config, then the added ones in the order of
+// the new one. A class listed twice in the same category is matched by its first listing.
+pub fn compare(old: &[Token], new: &[Token]) -> Vec<Change> {
+    let mut listed: HashMap<(&str, &str, &str), &CategoryItem> = HashMap::new();
+    for (trader, category, item) in items(new) {
+        listed.entry((&trader.name.text, &category.name.text, &item.class)).or_insert(item);
+    }
+
+    let mut changes = Vec::new();
+    let mut matched: HashSet<(&str, &str, &str)> = HashSet::new();
+    for (trader, category, item) in items(old) {
+        let key = (trader.name.text.as_str(), category.name.text.as_str(), item.class.as_str());
+        if !matched.insert(key) {
+            continue;
+        }
+        let change = |kind| Change {
+            trader: trader.name.text.clone(),
+            category: category.name.text.clone(),
+            class: item.class.clone(),
+            kind,
+        };
+        match listed.get(&key) {
+            None => changes.push(change(ChangeKind::Removed)),
+            Some(other) => {
+                for ((field, old), (_, new)) in fields(item).into_iter().zip(fields(other)) {
+                    if old != new {
+                        changes.push(change(ChangeKind::Changed { field, old, new }));
+                    }
+                }
+            },
+        }
+    }
+
+    for (trader, category, item) in items(new) {
+        let key = (trader.name.text.as_str(), category.name.text.as_str(), item.class.as_str());
+        if matched.insert(key) {
+            changes.push(Change {
+                trader: trader.name.text.clone(),
+                category: category.name.text.clone(),
+                class: item.class.clone(),
+                kind: ChangeKind::Added,
+            });
+        }
+    }
+    changes
+}
+
+fn fields(item: &CategoryItem) -> [(&'static str, String); 4] {
+    [
+        ("amount", item.amount.clone()),
+        ("buy value", item.buy_value.clone()),
+        ("sell value", item.sell_value.clone()),
+        ("stock", item.stock.clone().unwrap_or_else(|| "none".to_string())),
+    ]
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::items::items;
+use crate::token::*;
+
+// A parsed config. It derefs to its tokens so everything taking a slice of tokens takes a
+// Document too, the accessors cover the lookups callers otherwise write by hand.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Document {
+    pub tokens: Vec<Token>,
+}
+
+impl Document {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Document { tokens }
+    }
+
+    pub fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+
+    pub fn traders(&self) -> impl Iterator<Item = &Trader> {
+        self.tokens.iter().filter_map(|t| match t {
+            Token::Trader(trader) => Some(trader),
+            _ => None,
+        })
+    }
+
+    pub fn currency_names(&self) -> impl Iterator<Item = &CurrencyName> {
+        self.tokens.iter().filter_map(|t| match t {
+            Token::CurrencyName(c) => Some(c),
+            _ => None,
+        })
+    }
+
+    pub fn open_files(&self) -> impl Iterator<Item = &OpenFile> {
+        self.tokens.iter().filter_map(|t| match t {
+            Token::OpenFile(o) => Some(o),
+            _ => None,
+        })
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (&Trader, &TraderCategory, &CategoryItem)> {
+        items(&self.tokens)
+    }
+
+    // Every comment in file order, both the ones on their own line and the ones after a value
+    pub fn comments(&self) -> impl Iterator<Item = &Comment> {
+        let mut comments = Vec::new();
+        for t in self.tokens.iter() {
+            match t {
+                Token::Comment(c) => comments.push(c),
+                Token::CurrencyName(c) => {
+                    comments.extend(c.name.comments());
+                    for currency in c.currencies.iter() {
+                        match currency {
+                            CurrencyToken::Comment(c) => comments.push(c),
+                            CurrencyToken::Currency(line) => comments.extend(line.comment.iter()),
+                        }
+                    }
+                },
+                Token::Trader(trader) => trader_comments(trader, &mut comments),
+                Token::OpenFile(OpenFile(line)) | Token::FileEnd(FileEnd(line)) => {
+                    comments.extend(line.comments());
+                },
+                Token::Trailing(_) | Token::BlankLine => (),
+            }
+        }
+        comments.into_iter()
+    }
+
+    pub fn find_trader(&self, name: &str) -> Option<&Trader> {
+        self.traders().find(|t| t.name.text == name)
+    }
+
+    pub fn find_trader_mut(&mut self, name: &str) -> Option<&mut Trader> {
+        self.tokens.iter_mut().find_map(|t| match t {
+            Token::Trader(trader) if trader.name.text == name => Some(trader),
+            _ => None,
+        })
+    }
+}
+
+fn trader_comments<'a>(trader: &'a Trader, comments: &mut Vec<&'a Comment>) {
+    comments.extend(trader.name.comments());
+    for c in trader.categories.iter() {
+        match c {
+            TraderCategoryToken::Comment(c) => comments.push(c),
+            TraderCategoryToken::TraderCategory(category) => {
+                comments.extend(category.name.comments());
+                for i in category.items.iter() {
+                    match i {
+                        CategoryItemToken::Comment(c) => comments.push(c),
+                        CategoryItemToken::CategoryItem(item) => comments.extend(item.comment.iter()),
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl Deref for Document {
+    type Target = Vec<Token>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tokens
+    }
+}
+
+impl DerefMut for Document {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tokens
+    }
+}
+
+impl From<Vec<Token>> for Document {
+    fn from(tokens: Vec<Token>) -> Self {
+        Document::new(tokens)
+    }
+}
+
+impl From<Document> for Vec<Token> {
+    fn from(document: Document) -> Self {
+        document.tokens
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::format(&self.tokens))
+    }
+}
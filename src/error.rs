@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::{fmt, io};
+
+use crate::validate::Violation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    // Bytes from the start of the file, for editors that map positions that way
+    #[serde(default)]
+    pub offset: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnclosedTag { name: String, location: Location },
+    UnexpectedEnd { name: String, location: Location },
+    UnknownTag { name: String, location: Location },
+    UnexpectedText { line: String, location: Location },
+    MisplacedTag { name: &'static str, parent: &'static str, location: Location },
+    UnclosedComment { location: Location },
+    NestedComment { location: Location },
+    MissingCsvValues { found: usize, line: String, location: Location },
+    StockColumn { line: String, location: Location },
+    EmptyCurrency { location: Location },
+    EmptyCsvValue { position: usize, line: String, location: Location },
+    ContentAfterFileEnd { location: Location },
+    NestedBlock { name: String, block: Location, location: Location },
+    MissingFileEnd,
+    EmptyFile,
+    TraderNotFound { name: String },
+    Io { action: &'static str, source: io::Error },
+    InvalidPath { path: String, reason: &'static str },
+    Json(serde_json::Error),
+    Validation(Vec<Violation>),
+    MergeConflicts(Vec<Violation>),
+    Errors(Vec<ParseError>),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnclosedTag { name, location } => {
+                write!(f, "Tag <{}> at {} isn't closed before the end of the line", name, location)
+            },
+            ParseError::UnexpectedEnd { name, location } => {
+                write!(f, "Tag <{}> at {} isn't closed before the end of the file", name, location)
+            },
+            ParseError::UnknownTag { name, location } => {
+                write!(f, "Unknown tag <{}> at {}", name, location)
+            },
+            ParseError::UnexpectedText { line, location } => {
+                write!(f, "Line at {} isn't a tag or a comment: {}", location, line)
+            },
+            ParseError::MisplacedTag { name, parent, location } => {
+                write!(f, "Tag <{}> at {} must be inside a <{}>", name, location, parent)
+            },
+            ParseError::UnclosedComment { location } => {
+                write!(f, "Block comment starting at {} is never closed", location)
+            },
+            ParseError::NestedComment { location } => {
+                write!(f, "Nested block comment at {}, block comments can't contain /*", location)
+            },
+            ParseError::MissingCsvValues { found, line, location } => {
+                let hint = if *found < 4 { "probably a missing comma" } else { "probably an extra comma" };
+                write!(
+                    f,
+                    "Category item at {} has {} values instead of 4 or 5 (class, amount, buy value, sell value, stock), {}: {}",
+                    location, found, hint, line
+                )
+            },
+            ParseError::StockColumn { line, location } => {
+                write!(
+                    f,
+                    "Category item at {} has a stock value, vanilla traders only read 4 values (class, amount, buy value, sell value): {}",
+                    location, line
+                )
+            },
+            ParseError::EmptyCurrency { location } => {
+                write!(f, "Tag <Currency> at {} has no class name and value", location)
+            },
+            ParseError::EmptyCsvValue { position, line, location } => {
+                write!(f, "Value {} of the line at {} is empty, probably an extra comma: {}", position, location, line)
+            },
+            ParseError::ContentAfterFileEnd { location } => {
+                write!(f, "Content at {} after <FileEnd>, the trader stops reading at <FileEnd>", location)
+            },
+            ParseError::NestedBlock { name, block, location } => {
+                write!(
+                    f,
+                    "Tag <{}> at {} is indented inside the <CurrencyName> at {}, it ends that block and starts another",
+                    name, location, block
+                )
+            },
+            ParseError::MissingFileEnd => write!(f, "File is malformed, parsing didn't end with <FileEnd>"),
+            ParseError::EmptyFile => write!(f, "No trader config content found"),
+            ParseError::TraderNotFound { name } => write!(f, "No <Trader> matching \"{}\" found", name),
+            ParseError::Io { action, source } => write!(f, "{}: {}", action, source),
+            ParseError::InvalidPath { path, reason } => write!(f, "{}: {}", reason, path),
+            ParseError::Json(err) => write!(f, "Error processing JSON: {}", err),
+            ParseError::Validation(violations) => {
+                write!(f, "Validation failed with {} problems", violations.len())?;
+                for v in violations.iter() {
+                    write!(f, "\n    {}", v)?;
+                }
+                Ok(())
+            },
+            ParseError::MergeConflicts(conflicts) => {
+                write!(f, "Merge failed with {} conflicting items", conflicts.len())?;
+                for c in conflicts.iter() {
+                    write!(f, "\n    {}", c)?;
+                }
+                Ok(())
+            },
+            ParseError::Errors(errors) => {
+                write!(f, "Parsing failed with {} errors", errors.len())?;
+                for err in errors.iter() {
+                    write!(f, "\n    {}", err)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+// One problem as reported to tools, the location is missing for errors about the whole file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub kind: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+impl ParseError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::UnclosedTag { .. } => "unclosed_tag",
+            ParseError::UnexpectedEnd { .. } => "unexpected_end",
+            ParseError::UnknownTag { .. } => "unknown_tag",
+            ParseError::UnexpectedText { .. } => "unexpected_text",
+            ParseError::MisplacedTag { .. } => "misplaced_tag",
+            ParseError::UnclosedComment { .. } => "unclosed_comment",
+            ParseError::NestedComment { .. } => "nested_comment",
+            ParseError::MissingCsvValues { .. } => "missing_csv_values",
+            ParseError::StockColumn { .. } => "stock_column",
+            ParseError::EmptyCurrency { .. } => "empty_currency",
+            ParseError::EmptyCsvValue { .. } => "empty_csv_value",
+            ParseError::ContentAfterFileEnd { .. } => "content_after_file_end",
+            ParseError::NestedBlock { .. } => "nested_block",
+            ParseError::MissingFileEnd => "missing_file_end",
+            ParseError::EmptyFile => "empty_file",
+            ParseError::TraderNotFound { .. } => "trader_not_found",
+            ParseError::Io { .. } => "io",
+            ParseError::InvalidPath { .. } => "invalid_path",
+            ParseError::Json(_) => "json",
+            ParseError::Validation(_) => "validation",
+            ParseError::MergeConflicts(_) => "merge_conflict",
+            ParseError::Errors(_) => "errors",
+        }
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            ParseError::UnclosedTag { location, .. }
+            | ParseError::UnexpectedEnd { location, .. }
+            | ParseError::UnknownTag { location, .. }
+            | ParseError::UnexpectedText { location, .. }
+            | ParseError::MisplacedTag { location, .. }
+            | ParseError::UnclosedComment { location }
+            | ParseError::NestedComment { location }
+            | ParseError::MissingCsvValues { location, .. }
+            | ParseError::StockColumn { location, .. }
+            | ParseError::EmptyCurrency { location }
+            | ParseError::EmptyCsvValue { location, .. }
+            | ParseError::ContentAfterFileEnd { location }
+            | ParseError::NestedBlock { location, .. } => Some(*location),
+            _ => None,
+        }
+    }
+
+    // Validation, merge and collected parse errors hold several problems, each one is reported on its own
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = |message: String, location: Option<Location>| Diagnostic {
+            kind: self.kind().to_string(),
+            message,
+            line: location.map(|l| l.line),
+            column: location.map(|l| l.column),
+            offset: location.map(|l| l.offset),
+        };
+        match self {
+            ParseError::Validation(violations) | ParseError::MergeConflicts(violations) => {
+                violations.iter().map(|v| diagnostic(v.to_string(), None)).collect()
+            },
+            ParseError::Errors(errors) => errors.iter().flat_map(|err| err.diagnostics()).collect(),
+            _ => vec![diagnostic(self.to_string(), self.location())],
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            ParseError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::items::items;
+use crate::token::*;
+
+const CSV_HEADER: [&str; 8] = ["trader", "category", "class", "amount", "buy_value", "sell_value", "stock", "comment"];
+
+// One row per item with the trader and category it belongs to, the trailing comment of the item
+// goes in the last column. Comments on their own line are skipped.
+pub fn to_csv(tokens: &[Token], line_ending: LineEnding) -> String {
+    let mut out = String::new();
+    push_row(&mut out, &CSV_HEADER, line_ending);
+    for (trader, category, item) in items(tokens) {
+        let comment = item.comment.as_ref().map(|c| c.text.trim()).unwrap_or_default();
+        let row = [
+            trader.name.text.as_str(),
+            category.name.text.as_str(),
+            item.class.as_str(),
+            item.amount.as_str(),
+            item.buy_value.as_str(),
+            item.sell_value.as_str(),
+            item.stock.as_deref().unwrap_or_default(),
+            comment,
+        ];
+        push_row(&mut out, &row, line_ending);
+    }
+    out
+}
+
+fn push_row(out: &mut String, values: &[&str], line_ending: LineEnding) {
+    let values: Vec<String> = values.iter().map(|v| quote(v)).collect();
+    out.push_str(&values.join(","));
+    out.push_str(line_ending.as_str());
+}
+
+// Quoted as in RFC 4180, only when the value contains a separator, a quote or a line break
+fn quote(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
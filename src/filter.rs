@@ -0,0 +1,58 @@
+use crate::token::*;
+
+// How --only-trader compares names, case is ignored either way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraderMatch {
+    #[default]
+    Exact,
+    Substring,
+}
+
+impl TraderMatch {
+    pub fn matches(self, trader: &str, name: &str) -> bool {
+        match self {
+            TraderMatch::Exact => trader.to_lowercase() == name.to_lowercase(),
+            TraderMatch::Substring => trader.to_lowercase().contains(&name.to_lowercase()),
+        }
+    }
+}
+
+// Keeps the traders whose name matches with the comments and blank lines right before them. With
+// header the tokens before the first trader (OpenFile, currencies and their comments) and FileEnd
+// are kept too, as split does for each file, so the result can be read by the trader on its own.
+pub fn only_trader(tokens: Vec<Token>, name: &str, matching: TraderMatch, header: bool) -> Vec<Token> {
+    let mut kept = Vec::new();
+    let mut pending = Vec::new();
+    let mut seen_trader = false;
+
+    for t in tokens.into_iter() {
+        match t {
+            Token::Trader(trader) => {
+                seen_trader = true;
+                if matching.matches(&trader.name.text, name) {
+                    kept.append(&mut pending);
+                    kept.push(Token::Trader(trader));
+                } else {
+                    pending.clear();
+                }
+            },
+            Token::FileEnd(fe) => {
+                if header {
+                    kept.push(Token::FileEnd(fe));
+                }
+                break;
+            },
+            // Comments right before the first trader go with it, the others belong to the header
+            t if !seen_trader && !matches!(t, Token::Comment(_) | Token::BlankLine) => {
+                if header {
+                    kept.append(&mut pending);
+                    kept.push(t);
+                } else {
+                    pending.clear();
+                }
+            },
+            t => pending.push(t),
+        }
+    }
+    kept
+}
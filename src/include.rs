@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::document::Document;
+use crate::parser::{process_file, ParseOptions};
+use crate::read_maybe_gzip;
+
+// The root file and every file reachable through <OpenFile>, in the order they're first named.
+// Names are looked up in the folder of the root file as the trader does. Each file is read once,
+// so files naming themselves, as the usual header does, or each other don't loop. Files that
+// can't be read or parsed are listed without following them, reading them again reports why.
+pub fn resolve_includes(root: &Path, opts: &ParseOptions) -> Vec<PathBuf> {
+    let dir = root.parent().unwrap_or(Path::new(""));
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        if !seen.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+            continue;
+        }
+
+        let parsed = read_config(&path).and_then(|c| process_file(&c, opts).ok()).map(Document::new);
+        if let Some(document) = parsed {
+            let names: Vec<&str> = document.open_files()
+                .map(|o| o.0.text.as_str())
+                .filter(|name| !name.is_empty())
+                .collect();
+            // Pushed in reverse so the first name is followed first
+            pending.extend(names.into_iter().rev().map(|name| dir.join(name)));
+        }
+        files.push(path);
+    }
+    files
+}
+
+// Included files can be archived too, they're read as the formatter reads them
+fn read_config(path: &Path) -> Option<String> {
+    read_maybe_gzip(fs::read(path).ok()?).ok()
+}
@@ -0,0 +1,34 @@
+use crate::token::*;
+
+/// Every item of every trader along with the trader and category it's listed in, in file order.
+///
+/// ```
+/// let tokens = trader_config_formatter::parse(
+///     "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n        M4A1, *, 2000, 1000\n<FileEnd>\n",
+/// )?;
+/// let classes: Vec<&str> = trader_config_formatter::items(&tokens)
+///     .map(|(_, _, item)| item.class.as_str())
+///     .collect();
+/// assert_eq!(classes, ["AKM", "M4A1"]);
+/// # Ok::<(), trader_config_formatter::ParseError>(())
+/// ```
+pub fn items(tokens: &[Token]) -> impl Iterator<Item = (&Trader, &TraderCategory, &CategoryItem)> {
+    tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Trader(trader) => Some(trader),
+            _ => None,
+        })
+        .flat_map(|trader| {
+            trader.categories.iter().filter_map(move |c| match c {
+                TraderCategoryToken::TraderCategory(category) => Some((trader, category)),
+                TraderCategoryToken::Comment(_) => None,
+            })
+        })
+        .flat_map(|(trader, category)| {
+            category.items.iter().filter_map(move |i| match i {
+                CategoryItemToken::CategoryItem(item) => Some((trader, category, item)),
+                CategoryItemToken::Comment(_) => None,
+            })
+        })
+}
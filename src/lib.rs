@@ -0,0 +1,177 @@
+mod compare;
+mod document;
+mod error;
+mod export;
+mod filter;
+mod include;
+mod items;
+mod merge;
+mod normalize;
+mod parser;
+mod sort;
+mod split;
+mod stats;
+mod strip;
+mod token;
+mod validate;
+
+use std::io::{self, Read, Write};
+
+use flate2::read::GzDecoder;
+
+pub use compare::{compare, Change, ChangeKind};
+pub use document::Document;
+pub use error::{Diagnostic, Location, ParseError};
+pub use export::to_csv;
+pub use filter::{only_trader, TraderMatch};
+pub use include::resolve_includes;
+pub use token::{
+    CSVLine, CategoryItem, CategoryItemToken, ColumnOrder, Comment, CommentColumn, CommentStyle, CurrencyName,
+    CurrencyToken, FileEnd, Format, FormatOptions, Formatted, Indent, ItemField, Line, LineEnding, OpenFile, Token,
+    Trader, TraderCategory, TraderCategoryToken, Trailing, PADDING,
+};
+pub use items::items;
+pub use merge::merge;
+pub use normalize::{ambiguous_prices, normalize_prices};
+pub use parser::{ParseOptions, Tokens};
+pub use sort::{sort_categories, sort_items, sort_traders};
+pub use split::split;
+pub use stats::{duplicates, stats, Duplicate, Listing, Stats, TraderStats};
+pub use strip::strip_comments;
+pub use validate::{long_lines, profitable_resales, validate, validate_with, ValidateOptions, Violation};
+
+pub fn parse(contents: &str) -> Result<Document, ParseError> {
+    parse_with(contents, &ParseOptions::default())
+}
+
+// Like parse but the file has to end with <FileEnd>, as DayZ expects
+pub fn parse_strict(contents: &str) -> Result<Document, ParseError> {
+    parse_with(contents, &ParseOptions { strict: true, ..ParseOptions::default() })
+}
+
+pub fn parse_with(contents: &str, opts: &ParseOptions) -> Result<Document, ParseError> {
+    parser::process_file(contents, opts).map(Document::new)
+}
+
+// Like parse but every error is returned instead of only the first one, after an error the parser
+// skips ahead to the next line starting with a top-level tag
+pub fn parse_all(contents: &str) -> Result<Document, Vec<ParseError>> {
+    parse_all_with(contents, &ParseOptions::default())
+}
+
+pub fn parse_all_strict(contents: &str) -> Result<Document, Vec<ParseError>> {
+    parse_all_with(contents, &ParseOptions { strict: true, ..ParseOptions::default() })
+}
+
+pub fn parse_all_with(contents: &str, opts: &ParseOptions) -> Result<Document, Vec<ParseError>> {
+    parser::process_file_all(contents, opts).map(Document::new)
+}
+
+// Like parse_with but problems that don't stop parsing are returned with the document, such as a
+// block that looks nested in another one. With recover they include the errors of the skipped lines.
+pub fn parse_with_warnings(contents: &str, opts: &ParseOptions) -> Result<(Document, Vec<ParseError>), ParseError> {
+    parser::process_file_warning(contents, opts).map(|(tokens, warnings)| (Document::new(tokens), warnings))
+}
+
+// Lazily parses the tokens, the file doesn't need to be held as a Vec<Token> to be formatted
+pub fn parse_iter(contents: &str) -> Tokens<'_> {
+    parser::Tokens::new(contents)
+}
+
+// Reads a whole config from any reader, stdin and decompressed files are read the same way
+pub fn read_from<R: Read>(mut reader: R) -> Result<String, ParseError> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).map_err(|err| {
+        ParseError::Io { action: "Error reading input", source: err }
+    })?;
+    Ok(contents)
+}
+
+// The bytes every gzip file starts with
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Reads a config from the bytes of a file, compressed ones are recognized by their magic bytes
+// whatever they're named
+pub fn read_maybe_gzip(bytes: Vec<u8>) -> Result<String, ParseError> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        return read_from(GzDecoder::new(bytes.as_slice()));
+    }
+    String::from_utf8(bytes).map_err(|err| {
+        ParseError::Io { action: "Error reading file", source: io::Error::new(io::ErrorKind::InvalidData, err) }
+    })
+}
+
+// Parses and formats a whole config in one go
+pub fn format_file(contents: &str) -> Result<String, ParseError> {
+    format_file_with(contents, &FormatOptions::default())
+}
+
+pub fn format_file_with(contents: &str, opts: &FormatOptions) -> Result<String, ParseError> {
+    Ok(format_with(&parse(contents)?, opts))
+}
+
+pub fn format(tokens: &[Token]) -> String {
+    format_with(tokens, &FormatOptions::default())
+}
+
+pub fn format_with(tokens: &[Token], opts: &FormatOptions) -> String {
+    let mut out = String::new();
+    let mut previous = None;
+    for t in tokens.iter() {
+        out.push_str(&format_token(t, previous, opts));
+        if !matches!(t, Token::BlankLine) {
+            previous = Some(t);
+        }
+    }
+    out
+}
+
+// Formats a single token along with the spacing that separates it from the one before it: one
+// blank line after every block (currency, trader, file tags) unless nothing follows, none after a
+// comment so it stays attached to the block below. With preserve_blank_lines only the input's
+// own blank lines are kept.
+pub fn format_token(token: &Token, previous: Option<&Token>, opts: &FormatOptions) -> String {
+    match token {
+        Token::BlankLine if !opts.preserve_blank_lines => String::new(),
+        _ if is_separated(previous, opts) => format!("{}{}", opts.line_ending, Formatted(token, opts)),
+        _ => Formatted(token, opts).to_string(),
+    }
+}
+
+// Like format_with but the output is written as it's formatted instead of collected in a String
+pub fn write_formatted<W: Write>(tokens: &[Token], w: &mut W, opts: &FormatOptions) -> io::Result<()> {
+    let mut previous = None;
+    for t in tokens.iter() {
+        write_token(t, previous, w, opts)?;
+        if !matches!(t, Token::BlankLine) {
+            previous = Some(t);
+        }
+    }
+    Ok(())
+}
+
+// Like format_token but written straight to w
+pub fn write_token<W: Write>(
+    token: &Token,
+    previous: Option<&Token>,
+    w: &mut W,
+    opts: &FormatOptions,
+) -> io::Result<()> {
+    match token {
+        Token::BlankLine if !opts.preserve_blank_lines => Ok(()),
+        _ if is_separated(previous, opts) => write!(w, "{}{}", opts.line_ending, Formatted(token, opts)),
+        _ => write!(w, "{}", Formatted(token, opts)),
+    }
+}
+
+fn is_separated(previous: Option<&Token>, opts: &FormatOptions) -> bool {
+    !opts.preserve_blank_lines && previous.is_some_and(|p| !matches!(p, Token::Comment(_) | Token::BlankLine))
+}
+
+pub fn to_json(tokens: &[Token]) -> Result<String, ParseError> {
+    serde_json::to_string_pretty(tokens).map_err(ParseError::Json)
+}
+
+pub fn from_json(contents: &str) -> Result<Document, ParseError> {
+    serde_json::from_str(contents).map_err(ParseError::Json)
+}
@@ -1,784 +1,1622 @@
-#![feature(iter_advance_by)]
-
-use clap::{Arg, Command, ArgAction};
-use std::io::{stderr, Write};
-use std::{fs, fmt, process};
-use std::iter::Peekable;
-use std::path::Path;
-use core::str::Chars;
-
-const PADDING: usize =  60;
+use clap::{value_parser, Arg, ArgMatches, Command, ArgAction, ErrorKind, ValueSource};
+use flate2::{write::GzEncoder, Compression};
+use glob::Pattern;
+use serde::Serialize;
+use similar::TextDiff;
+use walkdir::WalkDir;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::{self, stderr, stdin, stdout, BufWriter, IsTerminal, Read, Write};
+use std::{env, fs, process};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use trader_config_formatter::{
+    ambiguous_prices, compare, duplicates, format_with, from_json, long_lines, merge, normalize_prices, only_trader,
+    parse_all_with, parse_with, parse_with_warnings, profitable_resales, read_from, read_maybe_gzip, resolve_includes,
+    sort_categories, sort_items, sort_traders, split, stats, strip_comments, to_csv, to_json, validate_with,
+    write_token, ColumnOrder, Comment, CommentColumn, CommentStyle, Diagnostic, Document, Duplicate, FormatOptions,
+    GZIP_MAGIC, Indent, LineEnding, ParseError, ParseOptions, Token, Tokens, TraderCategoryToken, TraderMatch,
+    ValidateOptions, Violation,
+};
+
+const STDIN_PATH: &str = "-";
+const CONFIG_FILE: &str = ".traderfmt.toml";
+// Flags a config file can give defaults for, under the same names
+const CONFIG_KEYS: [&str; 28] = [
+    "width", "indent-size", "indent-char", "no-pad", "align-comments", "comment-column", "comment-style",
+    "annotate-counts", "line-ending", "preserve-blank-lines", "delimiter", "column-order", "input-order", "strict",
+    "lenient-tags", "raw-comments", "raw-headers", "validate", "lint", "economy-check", "max-line-width",
+    "max-amount", "fail-on-warnings", "normalize-prices", "sort-categories", "sort-traders", "sort-items", "canonical",
+];
+
+// Editors often save in several steps, events closer together than this are handled as one change
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+const EXIT_PARSE: i32 = 1;
+const EXIT_CHANGED: i32 = 1;
+const EXIT_VALIDATION: i32 = 2;
+const EXIT_IO: i32 = 3;
+const EXIT_ARGS: i32 = 4;
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
 
 fn main() {
-    let m = Command::new("trade_config_formatter")
-        .arg(Arg::new("file").index(1).required(true).help("Input: The file to be processed"))
-        .arg(Arg::new("output")
-            .long("output")
-            .short('o')
-            .required(false)
-            .help("Output: The output file, by default overrides the input file")
-            .action(ArgAction::Set)
+    let mut cmd = Command::new("trade_config_formatter")
+        .args(args())
+        .subcommand(Command::new("format")
+            .about("Formats the files, the same as leaving out the subcommand")
+            .args(args())
         )
-        .arg(Arg::new("dry-run")
-            .long("dry-run")
-            .short('d')
-            .required(false)
-            .help("Dry Run: If present the command will just check the file is valid")
-            .action(ArgAction::SetTrue)
+        .subcommand(subcommand("validate", "Checks the files without printing them, as --validate does")
+            .after_help("Only prints the errors and warnings found, exiting with 2 when there are errors")
+        )
+        .subcommand(subcommand("convert", "Converts the files to JSON or CSV")
+            .arg(Arg::new("to")
+                .long("to")
+                .value_name("FORMAT")
+                .required(true)
+                .value_parser(["json", "csv"])
+                .help("To: The format to convert to, json for the token tree or csv for one row per item")
+                .action(ArgAction::Set)
+            )
         )
+        .subcommand(subcommand("stats", "Prints counts of traders, categories, items, currencies and comments"))
+        .subcommand(Command::new("compare")
+            .about("Compares two versions of a config item by item, ignoring formatting")
+            .arg(Arg::new("old")
+                .index(1)
+                .value_name("OLD")
+                .required(true)
+                .help("Old: The previous version of the config")
+                .action(ArgAction::Set)
+            )
+            .arg(Arg::new("new")
+                .index(2)
+                .value_name("NEW")
+                .required(true)
+                .help("New: The version of the config compared against OLD")
+                .action(ArgAction::Set)
+            )
+            .arg(Arg::new("format")
+                .long("format")
+                .short('f')
+                .required(false)
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("Format: The output format, json prints an array of the changes")
+                .action(ArgAction::Set)
+            )
+            .after_help("Prints one line per added (+), removed (-) or changed (~) item, matched by trader, category \
+                and class. Exits with 1 when the configs differ")
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
         .about("A tool to format DayZ trader config files")
-        .get_matches();
-
-    let file_path: &String = m.get_one("file").unwrap();
-
-    let output_file: &String = m.get_one("output").unwrap_or(file_path);
-
-    let dry: bool = *m.get_one("dry-run").unwrap_or(&false);
-    work(&file_path, &output_file, dry).unwrap_or_else(|err| {
-        stderr().write(format!("\nError processing file: {}\n\n", err).as_bytes()).unwrap();
-        process::exit(-1); 
-    });
-}
-
-fn work(file_path: &str, output_file_path: &str, dry: bool) -> Result<(), String> {
-    let contents = read_file(file_path)?;
-    let parsed = process_file(contents)?;
-
-    if !dry {
-        let mut out = String::new();
-        for p in parsed.iter() {
-            out.push_str(&format!("{}\n", p));
+        .after_help("EXIT CODES:
+    0    Success
+    1    A file failed to parse, or would change with --check, --diff or --dry-run
+    2    A file failed validation, had merge conflicts or had warnings with --fail-on-warnings
+    3    A file or directory couldn't be read or written
+    4    Invalid arguments, or no trader matched --only-trader");
+    let mut matches = cmd.try_get_matches_from_mut(env::args_os()).unwrap_or_else(|err| arg_error(err));
+    if let Some(path) = find_config().filter(|_| !matches!(matches.subcommand(), Some(("compare", _)))) {
+        let subcommand = matches.subcommand_name();
+        let m = matches.subcommand().map_or(&matches, |(_, m)| m);
+        let flags = config_flags(&path, &cmd, m, subcommand.and_then(subcommand_args)).unwrap_or_else(|err| {
+            arg_error(cmd.error(ErrorKind::InvalidValue, format!("Invalid {}: {}", path.display(), err)))
+        });
+        if !flags.is_empty() {
+            // Subcommands can't follow other arguments, so when there is one it's always first
+            let at = if subcommand.is_some() { 2 } else { 1 };
+            let mut argv: Vec<OsString> = env::args_os().collect();
+            argv.splice(at..at, flags.into_iter().map(OsString::from));
+            matches = cmd.try_get_matches_from_mut(argv).unwrap_or_else(|err| arg_error(err));
         }
-
-        write_file(output_file_path, &out)?;
     }
-
-    Ok(())
-}
-
-fn write_file(file_path: &str, content: &str) -> Result<(), String> {
-    let p = Path::new(file_path);
-    if let Some(parent) = p.parent() {
-        fs::create_dir_all(parent).map_err(|err| {
-            format!("Error creating parent directory of destination file: {}", err)
-        })?;
+    if let Some(("compare", m)) = matches.subcommand() {
+        process::exit(compare_files(m));
     }
-    if p.exists() {
-        fs::remove_file(file_path).map_err(|err| {
-            format!("Error deleting destination file: {}", err)
-        })?;
+    let (subcommand, m) = match matches.subcommand() {
+        Some((name, sub_matches)) => (Some(name), sub_matches),
+        None => (None, &matches),
+    };
+    if let Some(relevant) = subcommand.and_then(subcommand_args) {
+        let ignored = args().into_iter()
+            .map(|a| a.get_id())
+            .find(|id| !relevant.contains(id) && m.value_source(id) == Some(ValueSource::CommandLine));
+        if let Some(id) = ignored {
+            let message = format!("--{} can't be used with the {} subcommand", id, subcommand.unwrap_or_default());
+            arg_error(cmd.error(ErrorKind::ArgumentConflict, message));
+        }
+    }
+    let convert_to = m.try_get_one::<String>("to").ok().flatten().map(|t| t.as_str());
+
+    let color = match m.get_one::<String>("color").map(|c| c.as_str()) {
+        Some("always") => true,
+        Some("never") => false,
+        _ => stderr().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+    };
+
+    let json_errors = m.get_one::<String>("error-format").map(|f| f.as_str()) == Some("json");
+    let mut diagnostics: Vec<FileDiagnostic> = Vec::new();
+
+    let mut failed = 0;
+    // The exit code is the one of the first failure
+    let mut failure: Option<i32> = None;
+    let mut file_paths: Vec<String> = m.get_many::<String>("file").unwrap_or_default().cloned().collect();
+    if let Some(dir) = m.get_one::<String>("recursive") {
+        let pattern = Pattern::new(m.get_one::<String>("glob").unwrap()).unwrap_or_else(|err| {
+            arg_error(cmd.error(ErrorKind::InvalidValue, format!("Invalid --glob pattern: {}", err)));
+        });
+        for entry in find_files(dir, &pattern) {
+            match entry {
+                Ok(file_path) => file_paths.push(file_path),
+                Err(err) if json_errors => {
+                    let diagnostic = Diagnostic {
+                        kind: "io".to_string(),
+                        message: err,
+                        line: None,
+                        column: None,
+                        offset: None,
+                    };
+                    diagnostics.push(FileDiagnostic { file: dir.clone(), diagnostic });
+                    failure.get_or_insert(EXIT_IO);
+                    failed += 1;
+                },
+                Err(err) => {
+                    let message = format!("Error walking directory {}: {}", dir, err);
+                    stderr().write_all(format!("\n{}\n\n", paint(&message, RED, color)).as_bytes()).unwrap();
+                    failure.get_or_insert(EXIT_IO);
+                    failed += 1;
+                }
+            }
+        }
     }
 
-    fs::write(p, content).map_err(|err| {
-        format!("Error writing file: {:?}", err)
-    })
-}
-
-fn read_file(file_path: &str) -> Result<String, String> {
-
-    let p = Path::new(file_path);
-    if !p.exists() || !p.is_file() {
-        return Err(format!("The path provided is not valid"))
+    if file_paths.len() > 1 && m.contains_id("output") {
+        arg_error(cmd.error(ErrorKind::ArgumentConflict, "--output can only be used with a single file"));
     }
-    fs::read_to_string(p).map_err(|err| {
-        format!("Error reading file: {:?}", err)
-    })
 
-}
+    let in_place: bool = *m.get_one("in-place").unwrap_or(&false);
 
-#[derive(Debug, Clone)]
-struct Comment(String);
+    let mut format = FormatOptions::default();
+    if let Some(width) = m.get_one::<usize>("width") {
+        format.width = *width;
+    }
+    let indent_size = m.get_one::<usize>("indent-size").copied();
+    format.indent = match (m.get_one::<String>("indent-char").map(|c| c.as_str()), indent_size) {
+        (Some("tab"), size) => Indent::Tabs(size.unwrap_or(1)),
+        (Some(_), size) | (None, size @ Some(_)) => Indent::Spaces(size.unwrap_or(4)),
+        (None, None) => Indent::Legacy,
+    };
+    format.preserve_blank_lines = *m.get_one("preserve-blank-lines").unwrap_or(&false);
+    format.compact = *m.get_one("no-pad").unwrap_or(&false);
+    let delimiter = *m.get_one::<char>("delimiter").unwrap_or(&',');
+    format.delimiter = delimiter;
+    if let Some(column_order) = m.get_one::<ColumnOrder>("column-order") {
+        format.column_order = *column_order;
+    }
+    if let Some(column) = m.get_one::<usize>("comment-column") {
+        format.comment_column = CommentColumn::Fixed(*column);
+    } else if *m.get_one("align-comments").unwrap_or(&false) {
+        format.comment_column = CommentColumn::Widest;
+    }
+    format.annotate_counts = *m.get_one("annotate-counts").unwrap_or(&false);
+    format.comment_style = match m.get_one::<String>("comment-style").map(|s| s.as_str()) {
+        Some("slash") => Some(CommentStyle::Line),
+        Some("semicolon") => Some(CommentStyle::Semicolon),
+        Some("hash") => Some(CommentStyle::Hash),
+        _ => None,
+    };
+
+    let canonical: bool = *m.get_one("canonical").unwrap_or(&false);
+    let line_ending = match m.get_one::<String>("line-ending").map(|l| l.as_str()) {
+        Some("lf") => Some(LineEnding::Lf),
+        Some("crlf") => Some(LineEnding::CrLf),
+        // The line ending of the input would otherwise carry over
+        _ if canonical => Some(LineEnding::Lf),
+        _ => None,
+    };
+
+    let mode = if subcommand == Some("validate") {
+        Mode::Validate
+    } else if subcommand == Some("stats") {
+        Mode::Stats
+    } else if convert_to == Some("csv") {
+        Mode::ExportCsv
+    } else if *m.get_one("check").unwrap_or(&false) {
+        Mode::Check
+    } else if *m.get_one("diff").unwrap_or(&false) {
+        Mode::Diff
+    } else if *m.get_one("dry-run").unwrap_or(&false) {
+        Mode::DryRun
+    } else if *m.get_one("stats").unwrap_or(&false) {
+        Mode::Stats
+    } else if *m.get_one("export-csv").unwrap_or(&false) {
+        Mode::ExportCsv
+    } else if *m.get_one("list-traders").unwrap_or(&false) {
+        Mode::ListTraders
+    } else if *m.get_one("count-duplicates").unwrap_or(&false) {
+        Mode::CountDuplicates
+    } else if *m.get_one("extract-comments").unwrap_or(&false) {
+        Mode::ExtractComments
+    } else {
+        Mode::Format
+    };
+
+    let output_format = match convert_to.or_else(|| m.get_one::<String>("format").map(|f| f.as_str())) {
+        Some("json") => OutputFormat::Json,
+        _ => OutputFormat::Text,
+    };
+    let split_dir = m.get_one::<String>("split").cloned();
+    let json_conflict = in_place || split_dir.is_some() || matches!(mode, Mode::Check | Mode::Diff | Mode::ExportCsv);
+    if output_format == OutputFormat::Json && json_conflict {
+        arg_error(cmd.error(
+            ErrorKind::ArgumentConflict,
+            "--format json can't be used with --in-place, --check, --diff, --export-csv or --split",
+        ));
+    }
 
-impl fmt::Display for Comment {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "// {}", self.0)
+    let input_format = match m.get_one::<String>("from").map(|f| f.as_str()) {
+        Some("json") => InputFormat::Json,
+        _ => InputFormat::Text,
+    };
+    if input_format == InputFormat::Json && (in_place || mode == Mode::Check || mode == Mode::Diff) {
+        arg_error(cmd.error(ErrorKind::ArgumentConflict, "--from json can't be used with --in-place, --check or --diff"));
     }
-}
 
-#[derive(Debug)]
-struct Line {
-    text: String,
-    comment: Option<Comment>,
-}
+    let validate: bool = mode == Mode::Validate || *m.get_one("validate").unwrap_or(&false);
+
+    let sort_categories: bool = canonical || *m.get_one("sort-categories").unwrap_or(&false);
+    let sort_traders: bool = canonical || *m.get_one("sort-traders").unwrap_or(&false);
+
+    let settings = Settings {
+        mode,
+        format,
+        input_format,
+        output_format,
+        line_ending,
+        validate,
+        lint: validate || *m.get_one("lint").unwrap_or(&false),
+        economy_check: *m.get_one("economy-check").unwrap_or(&false),
+        max_line_width: *m.get_one::<usize>("max-line-width").unwrap_or(&120),
+        validation: ValidateOptions {
+            max_amount: *m.get_one::<u64>("max-amount").unwrap_or(&1_000_000),
+        },
+        merge: m.get_one::<String>("merge").cloned(),
+        split_dir,
+        sort_categories,
+        sort_traders,
+        sort_items: canonical || *m.get_one("sort-items").unwrap_or(&false),
+        parse: ParseOptions {
+            strict: *m.get_one("strict").unwrap_or(&false),
+            column_order: m.get_one::<ColumnOrder>("input-order").copied().unwrap_or_default(),
+            delimiter,
+            raw_comments: *m.get_one("raw-comments").unwrap_or(&false),
+            raw_headers: *m.get_one("raw-headers").unwrap_or(&false),
+            lenient_tags: *m.get_one("lenient-tags").unwrap_or(&false),
+            recover: *m.get_one("recover").unwrap_or(&false),
+        },
+        all_errors: *m.get_one("all-errors").unwrap_or(&false),
+        backup: if *m.get_one("backup").unwrap_or(&false) {
+            m.get_one::<String>("backup-suffix").cloned()
+        } else {
+            None
+        },
+        normalize_prices: canonical || *m.get_one("normalize-prices").unwrap_or(&false),
+        strip_comments: *m.get_one("strip-comments").unwrap_or(&false),
+        only_trader: m.get_one::<String>("only-trader").cloned(),
+        trader_match: match m.get_one::<String>("trader-match").map(|s| s.as_str()) {
+            Some("substring") => TraderMatch::Substring,
+            _ => TraderMatch::Exact,
+        },
+        with_header: *m.get_one("with-header").unwrap_or(&false),
+        list_categories: *m.get_one("list-categories").unwrap_or(&false),
+        top: *m.get_one::<usize>("top").unwrap_or(&10),
+        comment_locations: *m.get_one("comment-locations").unwrap_or(&false),
+    };
+
+    // Files named by several others or listed on the command line too are processed once
+    if *m.get_one("follow-includes").unwrap_or(&false) {
+        let mut followed: Vec<String> = Vec::new();
+        for file_path in file_paths.iter() {
+            let included = match file_path.as_str() {
+                STDIN_PATH => vec![file_path.clone()],
+                _ => resolve_includes(Path::new(file_path), &settings.parse)
+                    .into_iter()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect(),
+            };
+            for f in included {
+                if !followed.contains(&f) {
+                    followed.push(f);
+                }
+            }
+        }
+        file_paths = followed;
+    }
 
-impl fmt::Display for Line {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}\n", self.text, self.comment.as_ref().map(|c| format!("{}", c)).unwrap_or("".into()))
+    if file_paths.iter().any(|f| f == STDIN_PATH && matches!(output_for(f, m.get_one("output"), in_place), Output::InPlace)) {
+        arg_error(cmd.error(ErrorKind::ArgumentConflict, "Cannot write in place when reading from stdin"));
     }
-}
 
-#[derive(Debug)]
-struct CSVLine {
-    values: Vec<String>,
-    comment: Option<Comment>
-}
-
-impl fmt::Display for CSVLine {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let len = self.values.len();
-        for i in 0..len {
-            if let Some(v) = self.values.get(i) {
-                let mut str = String::from(v);
-                if i != len -1 {
-                    str.push(',');
-                }
-                write!(f, "{:0width$}", str, width = PADDING)?;
+    if !settings.can_replace_input() {
+        let output = m.get_one::<String>("output");
+        if file_paths.iter().any(|f| matches!(output_for(f, output, in_place), Output::InPlace)) {
+            let message = "Only a formatted config can be written over the input file";
+            arg_error(cmd.error(ErrorKind::ArgumentConflict, message));
+        }
+    }
 
-            };
+    if *m.get_one("watch").unwrap_or(&false) {
+        if file_paths.iter().any(|f| f == STDIN_PATH) {
+            arg_error(cmd.error(ErrorKind::ArgumentConflict, "--watch can't be used when reading from stdin"));
+        }
+        let files: Vec<(String, Output)> = file_paths.iter()
+            .map(|f| (f.clone(), output_for(f, m.get_one::<String>("output"), in_place)))
+            .collect();
+        if let Err(err) = watch(&files, &settings, color) {
+            let message = format!("Error watching files: {}", err);
+            stderr().write_all(format!("\n{}\n\n", paint(&message, RED, color)).as_bytes()).unwrap();
+            process::exit(exit_code(&err));
         }
+        return;
+    }
 
-        if let Some(c) = self.comment.as_ref() {
-            write!(f, " {}", c)?;
+    let fail_on_warnings = *m.get_one("fail-on-warnings").unwrap_or(&false);
+    let mut changed = false;
+    let mut processed = 0;
+    let mut changed_files = 0;
+    for file_path in file_paths.iter() {
+        let output = output_for(file_path, m.get_one::<String>("output"), in_place);
+
+        let mut warnings = Vec::new();
+        let result = work(file_path, &output, &settings, &mut warnings);
+        let warned = !warnings.is_empty();
+        for warning in warnings {
+            if json_errors {
+                let diagnostic = Diagnostic {
+                    kind: "warning".to_string(),
+                    message: warning.to_string(),
+                    line: None,
+                    column: None,
+                    offset: None,
+                };
+                diagnostics.push(FileDiagnostic { file: file_path.clone(), diagnostic });
+            } else {
+                let message = format!("Warning in file {}: {}", file_path, warning);
+                stderr().write_all(format!("{}\n", paint(&message, YELLOW, color)).as_bytes()).unwrap();
+            }
         }
 
-        write!(f, "\n")?;
+        match result {
+            Ok(_) if warned && fail_on_warnings => {
+                failure.get_or_insert(EXIT_VALIDATION);
+                failed += 1;
+            },
+            Ok(c) => {
+                changed |= c;
+                processed += 1;
+                changed_files += c as usize;
+            },
+            Err(err) if json_errors => {
+                diagnostics.extend(err.diagnostics().into_iter().map(|diagnostic| {
+                    FileDiagnostic { file: file_path.clone(), diagnostic }
+                }));
+                failure.get_or_insert(exit_code(&err));
+                failed += 1;
+            },
+            Err(err) => {
+                let message = format!("Error processing file {}: {}", file_path, err);
+                stderr().write_all(format!("\n{}\n\n", paint(&message, RED, color)).as_bytes()).unwrap();
+                failure.get_or_insert(exit_code(&err));
+                failed += 1;
+            }
+        }
+    }
 
-        Ok(())
+    if json_errors {
+        let json = serde_json::to_string_pretty(&diagnostics).unwrap();
+        stderr().write_all(format!("{}\n", json).as_bytes()).unwrap();
+    } else if m.contains_id("recursive") {
+        let summary = format!("Processed {} files, {} failed", processed, failed);
+        let summary = if failed > 0 { paint(&summary, YELLOW, color) } else { summary };
+        stderr().write_all(format!("{}\n", summary).as_bytes()).unwrap();
     }
-}
 
+    if let Some(code) = failure {
+        process::exit(code);
+    }
 
-#[derive(Debug)]
-enum CurrencyToken {
-    Comment(Comment),
-    Currency(CSVLine)
-}
+    if mode == Mode::DryRun {
+        println!("Would change {} of {} files", changed_files, processed);
+    }
 
-impl fmt::Display for CurrencyToken {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            CurrencyToken::Comment(c) => write!(f, "    {}", c),
-            CurrencyToken::Currency(c) => write!(f, "    <Currency> {}", c)
-        }
+    if changed && (mode == Mode::Check || mode == Mode::Diff || mode == Mode::DryRun) {
+        process::exit(EXIT_CHANGED);
     }
 }
 
-#[derive(Debug)]
-struct CurrencyName {
-    name: Line,
-    currencies: Vec<CurrencyToken>
+// The nearest .traderfmt.toml, in the current directory or one of its parents
+fn find_config() -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+    dir.ancestors().map(|d| d.join(CONFIG_FILE)).find(|p| p.is_file())
 }
 
-impl fmt::Display for CurrencyName {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<CurrencyName> {}", self.name)?;
-        for c in self.currencies.iter() {
-            write!(f, "    {}", c)?;
+// The flags set in a config file that weren't given on the command line, which takes precedence.
+// Flags the subcommand doesn't take are left out so one file serves every subcommand, and so are
+// flags that can't be used with one given on the command line.
+fn config_flags(
+    path: &Path,
+    cmd: &Command,
+    m: &ArgMatches,
+    relevant: Option<Vec<&str>>,
+) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let table: toml::Table = toml::from_str(&contents).map_err(|err| err.to_string())?;
+    let given: Vec<&str> = cmd.get_arguments()
+        .map(|a| a.get_id())
+        .filter(|id| m.value_source(id) == Some(ValueSource::CommandLine))
+        .collect();
+    let mut flags = Vec::new();
+    for (key, value) in table {
+        if !CONFIG_KEYS.contains(&key.as_str()) {
+            return Err(format!("unknown key {}, expected one of {}", key, CONFIG_KEYS.join(", ")));
+        }
+        let overridden = given.iter().any(|id| *id == key || conflicts(cmd, id, &key));
+        if overridden || relevant.as_ref().is_some_and(|r| !r.contains(&key.as_str())) {
+            continue;
+        }
+        match value {
+            toml::Value::Boolean(true) => flags.push(format!("--{}", key)),
+            toml::Value::Boolean(false) => (),
+            toml::Value::String(value) => flags.push(format!("--{}={}", key, value)),
+            toml::Value::Integer(value) => flags.push(format!("--{}={}", key, value)),
+            other => return Err(format!("{} has to be a string, a number or a boolean, not {}", key, other.type_str())),
         }
-        Ok(())
     }
+    Ok(flags)
 }
 
-#[derive(Debug)]
-struct CategoryItem {
-    class: String,
-    amount: String,
-    buy_value: String,
-    sell_value: String,
-    comment: Option<Comment>,
+// Conflicts are declared on one of the two arguments only, either way they can't be used together
+fn conflicts(cmd: &Command, a: &str, b: &str) -> bool {
+    let declared = |from: &str, to: &str| {
+        cmd.get_arguments()
+            .find(|arg| arg.get_id() == from)
+            .is_some_and(|arg| cmd.get_arg_conflicts_with(arg).iter().any(|c| c.get_id() == to))
+    };
+    declared(a, b) || declared(b, a)
 }
 
-impl TryFrom<&CSVLine> for CategoryItem {
-    type Error = String;
-
-    fn try_from(value: &CSVLine) -> Result<Self, Self::Error> {
-        if value.values.len() != 4 {
-            return Err(format!("Missing values to create a category item, probably a missing comma parsing {:?}", value))
+// Returns the exit code, the changes are printed to stdout and errors to stderr
+fn compare_files(m: &ArgMatches) -> i32 {
+    let mut documents = Vec::new();
+    for file_path in [m.get_one::<String>("old").unwrap(), m.get_one::<String>("new").unwrap()] {
+        match read_file(file_path).and_then(|contents| parse_with(&contents, &ParseOptions::default())) {
+            Ok(document) => documents.push(document),
+            Err(err) => {
+                let message = format!("Error processing file {}: {}", file_path, err);
+                stderr().write_all(format!("\n{}\n\n", message).as_bytes()).unwrap();
+                return exit_code(&err);
+            },
         }
+    }
 
-        Ok(CategoryItem {
-            class: value.values.get(0).unwrap().clone(),
-            amount: value.values.get(1).unwrap().clone(),
-            buy_value: value.values.get(2).unwrap().clone(),
-            sell_value: value.values.get(3).unwrap().clone(),
-            comment: value.comment.clone()
-        })
+    let changes = compare(&documents[0], &documents[1]);
+    if m.get_one::<String>("format").map(|f| f.as_str()) == Some("json") {
+        println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+    } else {
+        for change in changes.iter() {
+            println!("{}", change);
+        }
     }
+    if changes.is_empty() { 0 } else { EXIT_CHANGED }
 }
 
-impl fmt::Display for CategoryItem {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let class = format!("{},", self.class);
-        let amount = format!("{},", self.amount);
-        let buy_value = format!("{},", self.buy_value);
-        let sell_value = format!("{}", self.sell_value);
-        let comment = self.comment.as_ref().map(|c| c.to_string()).unwrap_or_default();
-
-        write!(f, "        {:60}{:10}{:10}{:10}{}", class, amount, buy_value, sell_value, comment)
+fn parse_delimiter(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (value, chars.next(), chars.next()) {
+        ("tab" | "\\t", _, _) => Ok('\t'),
+        (_, Some(c), None) if matches!(c, '"' | '/' | '<' | '\n' | '\r') => {
+            Err(format!("{:?} can't be used as a delimiter", c))
+        },
+        (_, Some(c), None) => Ok(c),
+        _ => Err("expected a single character or tab".to_string()),
     }
 }
 
-#[derive(Debug)]
-enum CategoryItemToken {
-    CategoryItem(CategoryItem),
-    Comment(Comment)
+// The arguments besides the input ones that each subcommand takes, None for all of them. The
+// others are hidden from its help and rejected.
+fn subcommand_args(name: &str) -> Option<Vec<&'static str>> {
+    let input = ["file", "recursive", "glob", "follow-includes", "error-format", "color"];
+    let parse = [
+        "from", "strict", "all-errors", "recover", "lenient-tags", "raw-comments", "raw-headers", "delimiter",
+        "input-order",
+    ];
+    let own: &[&'static str] = match name {
+        "validate" => &["lint", "economy-check", "fail-on-warnings", "max-line-width", "max-amount", "width", "no-pad"],
+        "convert" => &["output", "line-ending", "strip-comments"],
+        "stats" => &["output", "format"],
+        _ => return None,
+    };
+    Some(input.into_iter().chain(parse).chain(own.iter().copied()).collect())
 }
 
-impl fmt::Display for CategoryItemToken {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            CategoryItemToken::Comment(c) => write!(f, "        {}\n", c),
-            CategoryItemToken::CategoryItem(c) => write!(f, "{}\n", c)
-        }
-    }
+fn subcommand(name: &'static str, about: &'static str) -> Command<'static> {
+    let relevant = subcommand_args(name).unwrap_or_default();
+    Command::new(name)
+        .about(about)
+        .args(args().into_iter().map(|a| {
+            let hidden = !relevant.contains(&a.get_id());
+            a.hide(hidden)
+        }))
 }
 
-#[derive(Debug)]
-struct TraderCategory {
-    name: Line,
-    items: Vec<CategoryItemToken>,
+// Every argument of the bare command, subcommands take the ones relevant to them
+fn args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("file")
+            .index(1)
+            .required_unless_present("recursive")
+            .multiple_values(true)
+            .help("Input: The files to be processed, use - to read from stdin. Gzipped files are decompressed and written back compressed"),
+        Arg::new("output")
+            .long("output")
+            .short('o')
+            .required(false)
+            .help("Output: The output file, by default the formatted file is printed to stdout as it's parsed. The file is only written once the whole input parsed, a name ending in .gz is written compressed")
+            .conflicts_with("in-place")
+            .action(ArgAction::Set),
+        Arg::new("in-place")
+            .long("in-place")
+            .short('i')
+            .required(false)
+            .help("In Place: If present the input file is overwritten with the formatted output")
+            .action(ArgAction::SetTrue),
+        Arg::new("backup")
+            .long("backup")
+            .required(false)
+            .help("Backup: If present a file is copied to <file><suffix> before being overwritten, when that exists a counter is appended as in <file>.bak.1. Files the formatting doesn't change are left alone without a backup")
+            .action(ArgAction::SetTrue),
+        Arg::new("backup-suffix")
+            .long("backup-suffix")
+            .value_name("SUFFIX")
+            .required(false)
+            .default_value(".bak")
+            .help("Backup Suffix: The suffix added to the name of backups made with --backup")
+            .action(ArgAction::Set),
+        Arg::new("width")
+            .long("width")
+            .required(false)
+            .help("Width: The column width used to align values [default: 60]")
+            .value_parser(clap::value_parser!(usize))
+            .action(ArgAction::Set),
+        Arg::new("indent-size")
+            .long("indent-size")
+            .value_name("N")
+            .required(false)
+            .help("Indent Size: Every nesting level is indented by N spaces or tabs, 4 spaces or 1 tab by default")
+            .value_parser(clap::value_parser!(usize))
+            .action(ArgAction::Set),
+        Arg::new("indent-char")
+            .long("indent-char")
+            .required(false)
+            .value_parser(["space", "tab"])
+            .help("Indent Char: Indents nesting levels with spaces or tabs instead of the legacy 4, 8 and 16 spaces")
+            .action(ArgAction::Set),
+        Arg::new("no-pad")
+            .long("no-pad")
+            .alias("compact")
+            .required(false)
+            .help("No Pad: If present values are separated by a comma and a space instead of being padded to width")
+            .conflicts_with("width")
+            .action(ArgAction::SetTrue),
+        Arg::new("column-order")
+            .long("column-order")
+            .value_name("FIELDS")
+            .required(false)
+            .value_parser(value_parser!(ColumnOrder))
+            .help("Column Order: The order category item values are written in, as in class,buy_value,sell_value,amount")
+            .action(ArgAction::Set),
+        Arg::new("input-order")
+            .long("input-order")
+            .value_name("FIELDS")
+            .required(false)
+            .value_parser(value_parser!(ColumnOrder))
+            .help("Input Order: The order category item values are read in, by default class,amount,buy_value,sell_value")
+            .action(ArgAction::Set),
+        Arg::new("delimiter")
+            .long("delimiter")
+            .value_name("CHAR")
+            .required(false)
+            .value_parser(parse_delimiter)
+            .default_value(",")
+            .help("Delimiter: The character between item and currency values, tab or \\t for tab separated configs")
+            .action(ArgAction::Set),
+        Arg::new("lenient-tags")
+            .long("lenient-tags")
+            .required(false)
+            .help("Lenient Tags: If present tags are recognized in any casing, as in <trader> or <CATEGORY>, and written as <Trader> and <Category>")
+            .action(ArgAction::SetTrue),
+        Arg::new("raw-comments")
+            .long("raw-comments")
+            .required(false)
+            .help("Raw Comments: If present the spacing after // ; and # is kept as written, for headers and aligned notes")
+            .action(ArgAction::SetTrue),
+        Arg::new("raw-headers")
+            .long("raw-headers")
+            .required(false)
+            .help("Raw Headers: If present <OpenFile> lines are kept exactly as written, for headers with aligned columns")
+            .action(ArgAction::SetTrue),
+        Arg::new("align-comments")
+            .long("align-comments")
+            .required(false)
+            .help("Align Comments: If present item comments in a category line up one space past the widest item")
+            .action(ArgAction::SetTrue),
+        Arg::new("comment-column")
+            .long("comment-column")
+            .required(false)
+            .value_name("N")
+            .help("Comment Column: Item comments start N characters into the line, or one space past longer items")
+            .value_parser(clap::value_parser!(usize))
+            .conflicts_with("align-comments")
+            .action(ArgAction::Set),
+        Arg::new("comment-style")
+            .long("comment-style")
+            .required(false)
+            .value_parser(["slash", "semicolon", "hash"])
+            .help("Comment Style: Comments on their own line are written as // ; or # comments, block comments are kept")
+            .action(ArgAction::Set),
+        Arg::new("preserve-blank-lines")
+            .long("preserve-blank-lines")
+            .required(false)
+            .help("Preserve Blank Lines: If present blank lines between blocks are kept, collapsed to one")
+            .action(ArgAction::SetTrue),
+        Arg::new("merge")
+            .long("merge")
+            .required(false)
+            .value_name("OTHER")
+            .help("Merge: Adds the traders, categories and items of another config missing from the file, conflicting prices are reported")
+            .action(ArgAction::Set),
+        Arg::new("split")
+            .long("split")
+            .required(false)
+            .value_name("OUTDIR")
+            .help("Split: Writes each trader with the shared OpenFile, currencies and FileEnd to its own file in OUTDIR")
+            .conflicts_with_all(&["output", "in-place", "check", "diff", "dry-run", "stats", "export-csv"])
+            .action(ArgAction::Set),
+        Arg::new("only-trader")
+            .long("only-trader")
+            .required(false)
+            .value_name("NAME")
+            .help("Only Trader: Writes only the traders named NAME, ignoring case, with the comments right above them")
+            .action(ArgAction::Set),
+        Arg::new("trader-match")
+            .long("trader-match")
+            .required(false)
+            .value_name("MATCH")
+            .help("Trader Match: How --only-trader compares names, exact or substring to also write the traders whose name contains NAME")
+            .value_parser(["exact", "substring"])
+            .requires("only-trader")
+            .action(ArgAction::Set),
+        Arg::new("with-header")
+            .long("with-header")
+            .required(false)
+            .help("With Header: If present --only-trader keeps the OpenFile, currencies and FileEnd so the output is a config of its own")
+            .requires("only-trader")
+            .action(ArgAction::SetTrue),
+        Arg::new("normalize-prices")
+            .long("normalize-prices")
+            .required(false)
+            .help("Normalize Prices: If present buy and sell values like -1.0 or 0100 are rewritten as -1 or 100, 0 is taken for the -1 sentinel and becomes -1, thousands separators as in 1.000.000 are dropped, percentages keep their %, values mixing . and , or with a single separator as in 1.500 are warned about and other values that aren't whole numbers or percentages are errors")
+            .action(ArgAction::SetTrue),
+        Arg::new("annotate-counts")
+            .long("annotate-counts")
+            .required(false)
+            .help("Annotate Counts: If present each <Category> line ends its comment with the number of items in it, as in // [42 items], replacing the count from an earlier run")
+            .action(ArgAction::SetTrue),
+        Arg::new("strip-comments")
+            .long("strip-comments")
+            .required(false)
+            .help("Strip Comments: If present every comment is removed, the ones on their own line and the ones after a value")
+            .action(ArgAction::SetTrue),
+        Arg::new("sort-categories")
+            .long("sort-categories")
+            .required(false)
+            .help("Sort Categories: If present the categories of each trader are ordered by name")
+            .action(ArgAction::SetTrue),
+        Arg::new("sort-traders")
+            .long("sort-traders")
+            .required(false)
+            .help("Sort Traders: If present the traders are ordered by name")
+            .action(ArgAction::SetTrue),
+        Arg::new("sort-items")
+            .long("sort-items")
+            .required(false)
+            .help("Sort Items: If present the items of each category are ordered by class name")
+            .action(ArgAction::SetTrue),
+        Arg::new("canonical")
+            .long("canonical")
+            .required(false)
+            .help("Canonical: If present the output only depends on the content, as --sort-traders, --sort-categories, --sort-items and --normalize-prices together with LF line endings unless --line-ending is given")
+            .conflicts_with("preserve-blank-lines")
+            .action(ArgAction::SetTrue),
+        Arg::new("strict")
+            .long("strict")
+            .required(false)
+            .help("Strict: If present a text config that is empty, doesn't end with <FileEnd>, has content after it or has items with a fifth stock column is an error")
+            .action(ArgAction::SetTrue),
+        Arg::new("all-errors")
+            .long("all-errors")
+            .required(false)
+            .help("All Errors: If present every parse error is reported instead of only the first, after each one the parser skips to the next top-level tag")
+            .action(ArgAction::SetTrue),
+        Arg::new("recover")
+            .long("recover")
+            .required(false)
+            .help("Recover: If present category items and stray lines that fail to parse are written as // SKIPPED: comments and reported as warnings instead of failing the file")
+            .action(ArgAction::SetTrue),
+        Arg::new("dry-run")
+            .long("dry-run")
+            .short('d')
+            .required(false)
+            .help("Dry Run: If present nothing is written, the files that would change are listed and the command exits with 1 when there are any")
+            .action(ArgAction::SetTrue),
+        Arg::new("validate")
+            .long("validate")
+            .required(false)
+            .help("Validate: If present the command also checks item values (numbers, percentages or -1) and currency values, duplicated classes and <OpenFile> file names")
+            .action(ArgAction::SetTrue),
+        Arg::new("lint")
+            .long("lint")
+            .required(false)
+            .help("Lint: If present advisory checks, such as lines wider than --max-line-width, are printed as warnings without failing, --validate runs them too")
+            .action(ArgAction::SetTrue),
+        Arg::new("economy-check")
+            .long("economy-check")
+            .required(false)
+            .help("Economy Check: If present items selling for more than they're bought for are printed as warnings, items with -1 on either side are skipped")
+            .action(ArgAction::SetTrue),
+        Arg::new("fail-on-warnings")
+            .long("fail-on-warnings")
+            .required(false)
+            .help("Fail On Warnings: If present a file with warnings from --lint, --validate, --economy-check or --recover fails like one with errors")
+            .action(ArgAction::SetTrue),
+        Arg::new("max-line-width")
+            .long("max-line-width")
+            .value_name("N")
+            .required(false)
+            .default_value("120")
+            .help("Max Line Width: The width formatted item lines are warned about past with --lint or --validate")
+            .value_parser(value_parser!(usize))
+            .action(ArgAction::Set),
+        Arg::new("max-amount")
+            .long("max-amount")
+            .value_name("N")
+            .required(false)
+            .default_value("1000000")
+            .help("Max Amount: The largest item amount --validate accepts besides -1 and the quantity markers")
+            .value_parser(value_parser!(u64))
+            .action(ArgAction::Set),
+        Arg::new("check")
+            .long("check")
+            .required(false)
+            .help("Check: If present the command exits with 1 when the file is not already formatted")
+            .conflicts_with_all(&["dry-run", "output", "in-place"])
+            .action(ArgAction::SetTrue),
+        Arg::new("diff")
+            .long("diff")
+            .required(false)
+            .help("Diff: If present the command prints a unified diff of the changes and exits with 1 when there are any")
+            .conflicts_with_all(&["check", "dry-run", "output", "in-place"])
+            .action(ArgAction::SetTrue),
+        Arg::new("watch")
+            .long("watch")
+            .required(false)
+            .help("Watch: If present the files are processed again whenever they change on disk, printing a timestamped result, until Ctrl-C is pressed")
+            .action(ArgAction::SetTrue),
+        Arg::new("stats")
+            .long("stats")
+            .required(false)
+            .help("Stats: If present the command prints counts of traders, categories, items, currencies and comments")
+            .conflicts_with_all(&["check", "diff", "dry-run", "in-place"])
+            .action(ArgAction::SetTrue),
+        Arg::new("list-traders")
+            .long("list-traders")
+            .required(false)
+            .help("List Traders: If present the command prints the name of each trader on its own line, or an array of them with --format json")
+            .conflicts_with_all(&["check", "diff", "dry-run", "in-place", "stats"])
+            .action(ArgAction::SetTrue),
+        Arg::new("list-categories")
+            .long("list-categories")
+            .required(false)
+            .requires("list-traders")
+            .help("List Categories: If present --list-traders also prints the categories of each trader, indented under it")
+            .action(ArgAction::SetTrue),
+        Arg::new("count-duplicates")
+            .long("count-duplicates")
+            .required(false)
+            .help("Count Duplicates: If present the command prints the classes listed most often across all traders and categories, with where each listing is")
+            .conflicts_with_all(&["check", "diff", "dry-run", "in-place", "stats", "list-traders"])
+            .action(ArgAction::SetTrue),
+        Arg::new("top")
+            .long("top")
+            .value_name("N")
+            .required(false)
+            .requires("count-duplicates")
+            .default_value("10")
+            .help("Top: The number of classes --count-duplicates prints")
+            .value_parser(value_parser!(usize))
+            .action(ArgAction::Set),
+        Arg::new("extract-comments")
+            .long("extract-comments")
+            .required(false)
+            .help("Extract Comments: If present the command prints the text of every comment in file order, one per line, or an array of them with their line and column with --format json")
+            .conflicts_with_all(&["check", "diff", "dry-run", "in-place", "stats", "list-traders", "count-duplicates"])
+            .action(ArgAction::SetTrue),
+        Arg::new("comment-locations")
+            .long("comment-locations")
+            .required(false)
+            .requires("extract-comments")
+            .help("Comment Locations: If present --extract-comments prints the line and column of each comment before it")
+            .action(ArgAction::SetTrue),
+        Arg::new("export-csv")
+            .long("export-csv")
+            .required(false)
+            .help("Export CSV: If present the command prints every item as a trader,category,class,amount,buy_value,sell_value,stock,comment row")
+            .conflicts_with_all(&["check", "diff", "dry-run", "in-place", "stats", "list-traders"])
+            .action(ArgAction::SetTrue),
+        Arg::new("recursive")
+            .long("recursive")
+            .short('r')
+            .required(false)
+            .help("Recursive: A directory whose files matching --glob are processed, including subdirectories")
+            .conflicts_with("output")
+            .action(ArgAction::Set),
+        Arg::new("follow-includes")
+            .long("follow-includes")
+            .required(false)
+            .help("Follow Includes: If present the files named by <OpenFile> are processed too, looked up in the folder of the file given")
+            .conflicts_with("output")
+            .action(ArgAction::SetTrue),
+        Arg::new("glob")
+            .long("glob")
+            .required(false)
+            .requires("recursive")
+            .default_value("*.txt")
+            .help("Glob: The file name pattern used to pick files with --recursive")
+            .action(ArgAction::Set),
+        Arg::new("format")
+            .long("format")
+            .short('f')
+            .required(false)
+            .value_parser(["text", "json"])
+            .default_value("text")
+            .help("Format: The output format, json serializes the parsed config")
+            .action(ArgAction::Set),
+        Arg::new("from")
+            .long("from")
+            .required(false)
+            .value_parser(["text", "json"])
+            .default_value("text")
+            .help("From: The input format, json reads a config serialized with --format json")
+            .action(ArgAction::Set),
+        Arg::new("line-ending")
+            .long("line-ending")
+            .required(false)
+            .value_parser(["auto", "lf", "crlf"])
+            .default_value("auto")
+            .help("Line Ending: The line ending of the output, auto keeps the one most used in the input")
+            .action(ArgAction::Set),
+        Arg::new("error-format")
+            .long("error-format")
+            .value_name("FORMAT")
+            .required(false)
+            .value_parser(["human", "json"])
+            .default_value("human")
+            .help("Error Format: How errors are printed to stderr, json prints an array of every error with its kind and location")
+            .action(ArgAction::Set),
+        Arg::new("color")
+            .long("color")
+            .required(false)
+            .value_parser(["auto", "always", "never"])
+            .default_value("auto")
+            .help("Color: Whether errors are colored, auto colors them when stderr is a terminal and NO_COLOR isn't set")
+            .action(ArgAction::Set),
+    ]
 }
 
-impl fmt::Display for TraderCategory {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "    <Category> {}", self.name)?;
-        for c in self.items.iter() {
-            write!(f, "        {}", c)?;
-        }
-        Ok(())
+fn exit_code(err: &ParseError) -> i32 {
+    match err {
+        ParseError::Validation(_) | ParseError::MergeConflicts(_) => EXIT_VALIDATION,
+        ParseError::Io { .. } | ParseError::InvalidPath { .. } => EXIT_IO,
+        ParseError::TraderNotFound { .. } => EXIT_ARGS,
+        _ => EXIT_PARSE,
     }
 }
 
-#[derive(Debug)]
-enum TraderCategoryToken {
-    TraderCategory(TraderCategory),
-    Comment(Comment)
-}
-
-impl fmt::Display for TraderCategoryToken {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            TraderCategoryToken::Comment(c) => write!(f, "\t{}", c),
-            TraderCategoryToken::TraderCategory(c) => write!(f, "{}", c)
-        }
+// Help and version requests are printed as usual, actual argument errors exit with EXIT_ARGS
+fn arg_error(err: clap::Error) -> ! {
+    if !err.use_stderr() {
+        err.exit();
     }
+    let _ = err.print();
+    process::exit(EXIT_ARGS)
 }
 
-#[derive(Debug)]
-struct Trader {
-    name: Line,
-    categories: Vec<TraderCategoryToken>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Format,
+    DryRun,
+    Check,
+    Diff,
+    Stats,
+    ExportCsv,
+    ListTraders,
+    CountDuplicates,
+    ExtractComments,
+    // Nothing is printed besides errors and warnings
+    Validate,
 }
 
-impl fmt::Display for Trader {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<Trader> {}", self.name)?;
-        for c in self.categories.iter() {
-            write!(f, "{}", c)?;
-        }
-        Ok(())
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    Text,
+    Json,
 }
 
-#[derive(Debug)]
-struct OpenFile(Line);
-
-impl fmt::Display for OpenFile {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<OpenFile> {}", self.0)
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
 }
 
 #[derive(Debug)]
-struct FileEnd(Line);
-
-impl fmt::Display for FileEnd {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<FileEnd> {}", self.0)
-    }
+struct Settings {
+    mode: Mode,
+    format: FormatOptions,
+    input_format: InputFormat,
+    output_format: OutputFormat,
+    // None keeps the line ending used the most in each input file
+    line_ending: Option<LineEnding>,
+    validate: bool,
+    // Advisory checks reported as warnings, which don't fail the file
+    lint: bool,
+    economy_check: bool,
+    max_line_width: usize,
+    validation: ValidateOptions,
+    merge: Option<String>,
+    split_dir: Option<String>,
+    sort_categories: bool,
+    sort_traders: bool,
+    sort_items: bool,
+    parse: ParseOptions,
+    all_errors: bool,
+    normalize_prices: bool,
+    strip_comments: bool,
+    only_trader: Option<String>,
+    trader_match: TraderMatch,
+    // --only-trader keeps the tokens around the traders too
+    with_header: bool,
+    // --list-traders prints the categories of each trader too
+    list_categories: bool,
+    // How many classes --count-duplicates prints
+    top: usize,
+    // --extract-comments prints where each comment is
+    comment_locations: bool,
+    // Suffix of the copy made before a file is overwritten
+    backup: Option<String>,
 }
 
-#[derive(Debug)]
-enum Token {
-    Comment(Comment),
-    CurrencyName(CurrencyName),
-    Trader(Trader),
-    OpenFile(OpenFile),
-    FileEnd(FileEnd)
-}
-
-impl fmt::Display for Token {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Token::Comment(c) => write!(f, "{}", c),
-            Token::CurrencyName(c) => write!(f, "{}", c),
-            Token::Trader(t) => write!(f, "{}", t),
-            Token::OpenFile(o) => write!(f, "{}", o),
-            Token::FileEnd(fe) => write!(f, "{}", fe)
+impl Settings {
+    // Stats, listings and JSON written over the input would lose the config
+    fn can_replace_input(&self) -> bool {
+        match self.mode {
+            Mode::Format => self.output_format == OutputFormat::Text,
+            Mode::Stats | Mode::ExportCsv | Mode::ListTraders | Mode::CountDuplicates | Mode::ExtractComments => false,
+            Mode::DryRun | Mode::Check | Mode::Diff | Mode::Validate => true,
         }
     }
-}
 
-fn process_file(contents: String) -> Result<Vec<Token>, String> {
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut chars = contents.chars().peekable();
-    while let Some(_) = chars.peek() {
-        if let Some(t) = parse_token(&mut chars)? {
-            tokens.push(t);
-        } else {
-            chars.next();
-        }
+    // Plain formatting doesn't need the whole file parsed first
+    fn streams(&self) -> bool {
+        self.mode == Mode::Format
+            && self.input_format == InputFormat::Text
+            && self.output_format == OutputFormat::Text
+            && !self.validate
+            && !self.lint
+            && !self.economy_check
+            && !self.parse.recover
+            && self.merge.is_none()
+            && self.split_dir.is_none()
+            && !self.sort_categories
+            && !self.sort_traders
+            && !self.sort_items
+            && !self.parse.strict
+            && !self.all_errors
+            && !self.normalize_prices
+            && !self.strip_comments
+            && self.only_trader.is_none()
     }
+}
 
-    Ok(tokens)
-
-    // if let Some(Token::FileEnd(_)) = tokens.last() {
-    //     Ok(tokens)
-    // } else {
-    //     Err("File is malformed, parsing didn't end with <FileEnd>".into())
-    // }
+#[derive(Debug, Serialize)]
+struct FileDiagnostic {
+    file: String,
+    #[serde(flatten)]
+    diagnostic: Diagnostic,
 }
 
-fn parse_token(chars: &mut Peekable<Chars>) -> Result<Option<Token>, String> {
-    consume_spaces(chars)?;
-    if let Some(c) = parse_comment(chars)? {
-        return Ok(Some(Token::Comment(c)));
-    }
+#[derive(Debug)]
+enum Output {
+    Stdout,
+    InPlace,
+    File(String),
+}
 
-    if let Some(c) = parse_currency_name(chars)? {
-        return Ok(Some(Token::CurrencyName(c)));
-    }
+// Links are followed so configs shared between servers are picked up, walkdir reports a loop as
+// an error instead of descending into it forever.
+fn find_files<'a>(dir: &str, pattern: &'a Pattern) -> impl Iterator<Item = Result<String, String>> + 'a {
+    WalkDir::new(dir)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(move |entry| match entry {
+            Ok(e) if e.file_type().is_file() && pattern.matches(&e.file_name().to_string_lossy()) => {
+                Some(Ok(e.path().to_string_lossy().into_owned()))
+            },
+            Ok(_) => None,
+            Err(err) => Some(Err(err.to_string())),
+        })
+}
 
-    if let Some(t) = parse_trader(chars)? {
-        return Ok(Some(Token::Trader(t)));
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", code, text, RESET)
+    } else {
+        text.to_string()
     }
+}
 
-    if let Some(o) = parse_open_file(chars)? {
-        return Ok(Some(Token::OpenFile(o)))
+fn output_for(file_path: &str, output: Option<&String>, in_place: bool) -> Output {
+    match output {
+        Some(o) if same_path(o, file_path) => Output::InPlace,
+        Some(o) => Output::File(o.clone()),
+        None if in_place => Output::InPlace,
+        None => Output::Stdout,
     }
+}
 
-    if let Some(fe) = parse_file_end(chars)? {
-        return Ok(Some(Token::FileEnd(fe)))
+fn same_path(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
     }
-
-    Ok(None)
 }
 
-fn parse_file_end(chars: &mut Peekable<Chars>) -> Result<Option<FileEnd>, String> {
-    consume_spaces(chars)?;
-    let c0 = chars.peek();
-
-    if Some(&'<') != c0 {
-        return Ok(None);
+// Warnings are collected whether or not the file fails
+fn work(
+    file_path: &str,
+    output: &Output,
+    settings: &Settings,
+    warnings: &mut Vec<Violation>,
+) -> Result<bool, ParseError> {
+    let contents = read_file(file_path)?;
+    let opts = FormatOptions {
+        line_ending: settings.line_ending.unwrap_or_else(|| LineEnding::detect(&contents)),
+        ..settings.format.clone()
+    };
+    if settings.streams() {
+        let mut tokens = Tokens::with_options(&contents, &settings.parse);
+        stream(&mut tokens, file_path, output, &opts, settings.backup.as_deref())?;
+        warnings.extend(tokens.take_warnings().into_iter().map(parse_warning));
+        return Ok(false);
     }
 
+    let mut parsed = parse_input(&contents, settings, warnings)?;
 
-
-    let mut txt: String = String::new();
-
-    let mut internal_idx = 0;
-    let mut ichars = chars.clone();
-    ichars.next();
-    for c in ichars {
-        match c {
-            '>' | '/' => break,
-            '\n' | '\r' => return Err(format!("Error parsing file end, unclosed tag")),
-            c => txt.push(c)
+    if let Some(other_path) = settings.merge.as_ref() {
+        let other = parse_input(&read_file(other_path)?, settings, warnings)?;
+        let conflicts = merge(&mut parsed, other.into_tokens());
+        if !conflicts.is_empty() {
+            return Err(ParseError::MergeConflicts(conflicts));
         }
-        internal_idx = internal_idx + 1;
     }
 
-    if txt != "FileEnd" {
-        return Ok(None)
+    if settings.strip_comments {
+        strip_comments(&mut parsed);
     }
 
-    chars.advance_by(internal_idx + 2).map_err(|_| {
-        format!("Error advancing index parsing file end")
-    })?;
-    
-    let line = parse_line(chars)?;
-
-    Ok(Some(FileEnd(line)))
-
-}
-
-fn parse_open_file(chars: &mut Peekable<Chars>) -> Result<Option<OpenFile>, String> {
-    consume_spaces(chars)?;
-    let c0 = chars.peek();
-
-    if Some(&'<') != c0 {
-        return Ok(None);
+    if settings.normalize_prices {
+        warnings.extend(ambiguous_prices(&parsed));
+        let violations = normalize_prices(&mut parsed);
+        if !violations.is_empty() {
+            return Err(ParseError::Validation(violations));
+        }
     }
 
+    if settings.lint {
+        warnings.extend(long_lines(&parsed, &opts, settings.max_line_width));
+    }
+    if settings.economy_check {
+        warnings.extend(profitable_resales(&parsed));
+    }
 
-
-    let mut txt: String = String::new();
-
-    let mut internal_idx = 0;
-    let mut ichars = chars.clone();
-    ichars.next();
-    for c in ichars {
-        match c {
-            '>' | '/' => break,
-            '\n' | '\r' => return Err(format!("Error parsing openfile, unclosed tag")),
-            c => txt.push(c)
+    if settings.validate {
+        let violations = validate_with(&parsed, &settings.validation);
+        if !violations.is_empty() {
+            return Err(ParseError::Validation(violations));
         }
-        internal_idx = internal_idx + 1;
     }
 
-    if txt != "OpenFile" {
-        return Ok(None)
+    if settings.sort_categories {
+        sort_categories(&mut parsed);
     }
-
-    chars.advance_by(internal_idx + 2).map_err(|_| {
-        format!("Error advancing index parsing open file")
-    })?;
-    
-    let line = parse_line(chars)?;
-
-    Ok(Some(OpenFile(line)))
-}
-
-fn parse_trader_category_item_token(chars: &mut Peekable<Chars>) -> Result<Option<CategoryItemToken>, String> {
-    consume_spaces(chars)?;
-
-    if let Some(comment) = parse_comment(chars)? {
-        return Ok(Some(CategoryItemToken::Comment(comment)));
+    if settings.sort_traders {
+        sort_traders(&mut parsed);
     }
-
-    if let Some(item) = parse_csv_line(chars)? {
-        let item = CategoryItem::try_from(&item)?;
-        return Ok(Some(CategoryItemToken::CategoryItem(item)));
+    if settings.sort_items {
+        sort_items(&mut parsed);
     }
 
-    Ok(None)
-}
-
-fn parse_trader_category(chars: &mut Peekable<Chars>) -> Result<Option<TraderCategory>, String> {
-    consume_spaces(chars)?;
-    let c0 = chars.peek();
-
-    if Some(&'<') != c0 {
-        return Ok(None);
+    if let Some(name) = settings.only_trader.as_ref() {
+        let tokens = only_trader(parsed.into_tokens(), name, settings.trader_match, settings.with_header);
+        if !tokens.iter().any(|t| matches!(t, Token::Trader(_))) {
+            return Err(ParseError::TraderNotFound { name: name.clone() });
+        }
+        parsed = Document::new(tokens);
     }
 
-
-
-    let mut txt: String = String::new();
-
-    let mut internal_idx = 0;
-    let mut ichars = chars.clone();
-    ichars.next();
-    for c in ichars {
-        match c {
-            '>' | '/' => break,
-            '\n' | '\r' => return Err(format!("Error parsing trader category name, unclosed tag")),
-            c => txt.push(c)
+    if let Some(dir) = settings.split_dir.as_ref() {
+        for (name, tokens) in split(parsed.into_tokens()) {
+            let path = Path::new(dir).join(name).to_string_lossy().to_string();
+            write_file(&path, &format_with(&tokens, &opts), is_gzip_path(&path), None)?;
         }
-        internal_idx = internal_idx + 1;
+        return Ok(false);
     }
 
-    if txt != "Category" {
-        return Ok(None)
+    let out = match (settings.mode, settings.output_format) {
+        (Mode::Stats, OutputFormat::Text) => stats(&parsed).to_string(),
+        (Mode::Stats, OutputFormat::Json) => {
+            let json = serde_json::to_string_pretty(&stats(&parsed)).map_err(ParseError::Json)?;
+            format!("{}{}", json, opts.line_ending)
+        },
+        (Mode::ExportCsv, _) => to_csv(&parsed, opts.line_ending),
+        (Mode::ListTraders, OutputFormat::Text) => list_traders(&parsed, settings.list_categories, opts.line_ending),
+        (Mode::ListTraders, OutputFormat::Json) => {
+            let json = if settings.list_categories {
+                serde_json::to_string_pretty(&trader_listings(&parsed))
+            } else {
+                serde_json::to_string_pretty(&parsed.traders().map(|t| &t.name.text).collect::<Vec<_>>())
+            };
+            format!("{}{}", json.map_err(ParseError::Json)?, opts.line_ending)
+        },
+        (Mode::CountDuplicates, OutputFormat::Text) => count_duplicates(&parsed, settings.top, opts.line_ending),
+        (Mode::CountDuplicates, OutputFormat::Json) => {
+            let top: Vec<Duplicate> = duplicates(&parsed).into_iter().take(settings.top).collect();
+            format!("{}{}", serde_json::to_string_pretty(&top).map_err(ParseError::Json)?, opts.line_ending)
+        },
+        (Mode::ExtractComments, OutputFormat::Text) => {
+            extract_comments(&parsed, settings.comment_locations, opts.line_ending)
+        },
+        (Mode::ExtractComments, OutputFormat::Json) => {
+            let comments: Vec<ExtractedComment> = parsed.comments().map(ExtractedComment::from).collect();
+            format!("{}{}", serde_json::to_string_pretty(&comments).map_err(ParseError::Json)?, opts.line_ending)
+        },
+        (_, OutputFormat::Text) => format_with(&parsed, &opts),
+        (_, OutputFormat::Json) => format!("{}{}", to_json(&parsed)?, opts.line_ending),
+    };
+    // A dry run writing elsewhere would only change the output file when its contents differ
+    let changed = match (settings.mode, output) {
+        (Mode::DryRun, Output::File(output_file_path)) => {
+            read_file(output_file_path).map_or(true, |current| is_changed(&current, &out))
+        },
+        _ => is_changed(&contents, &out),
+    };
+
+    match settings.mode {
+        Mode::Format | Mode::Stats | Mode::ExportCsv | Mode::ListTraders | Mode::CountDuplicates
+        | Mode::ExtractComments => match output {
+            Output::Stdout => print!("{}", out),
+            Output::InPlace => write_file(file_path, &out, is_gzip_file(file_path), settings.backup.as_deref())?,
+            Output::File(output_file_path) => {
+                write_file(output_file_path, &out, is_gzip_path(output_file_path), None)?
+            },
+        },
+        Mode::Diff if changed => {
+            let diff = TextDiff::from_lines(&contents, &out);
+            print!("{}", diff.unified_diff().header(file_path, &format!("{} (formatted)", file_path)));
+        },
+        Mode::DryRun => match output {
+            Output::Stdout if changed => println!("Would reformat {}", file_path),
+            Output::InPlace if changed => println!("Would rewrite {}", file_path),
+            Output::File(output_file_path) if changed => println!("Would write {}", output_file_path),
+            _ => (),
+        },
+        _ => ()
     }
 
+    Ok(changed)
+}
 
-    chars.advance_by(internal_idx + 2).map_err(|_| {
-        format!("Error advancing index parsing trader category name")
-    })?;
+enum WatchEvent {
+    Changed(Vec<PathBuf>),
+    Stop,
+}
 
-    let line = parse_line(chars)?;
+struct WatchedFile<'a> {
+    path: PathBuf,
+    file_path: &'a str,
+    output: &'a Output,
+    // What the file contained after it was last processed, writing it in place triggers another event
+    contents: Option<Vec<u8>>,
+}
 
-    let mut items = Vec::new();
-    while let Some(item) = parse_trader_category_item_token(chars)? {
-        items.push(item);
+// Files are processed once and then again after every change until Ctrl-C. Saving often replaces
+// the file, so the directories holding them are watched rather than the files themselves.
+fn watch(files: &[(String, Output)], settings: &Settings, color: bool) -> Result<(), ParseError> {
+    let watch_error = |err| ParseError::Io { action: "Error watching file", source: err };
+    let (sender, receiver) = mpsc::channel();
+    let stop = sender.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(WatchEvent::Stop);
+    }).map_err(|err| watch_error(io::Error::other(err)))?;
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+        Ok(e) if e.kind.is_create() || e.kind.is_modify() => {
+            let _ = sender.send(WatchEvent::Changed(e.paths));
+        },
+        _ => (),
+    }).map_err(|err| watch_error(io::Error::other(err)))?;
+
+    let mut watched = Vec::new();
+    for (file_path, output) in files.iter() {
+        let path = fs::canonicalize(file_path).map_err(watch_error)?;
+        let dir = path.parent().unwrap_or(&path);
+        watcher.watch(dir, RecursiveMode::NonRecursive).map_err(|err| watch_error(io::Error::other(err)))?;
+        report(file_path, output, settings, color);
+        watched.push(WatchedFile { contents: fs::read(&path).ok(), path, file_path, output });
     }
 
-    Ok(Some(TraderCategory {
-        name: line,
-        items
-    }))
-}
-
-fn parse_trader_category_token(chars: &mut Peekable<Chars>) -> Result<Option<TraderCategoryToken>, String> {
-    consume_spaces(chars)?;
+    while let Ok(WatchEvent::Changed(paths)) = receiver.recv() {
+        let mut changed: HashSet<PathBuf> = paths.into_iter().collect();
+        loop {
+            match receiver.recv_timeout(WATCH_DEBOUNCE) {
+                Ok(WatchEvent::Changed(paths)) => changed.extend(paths),
+                Ok(WatchEvent::Stop) | Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
 
-    if let Some(comment) = parse_comment(chars)? {
-        return Ok(Some(TraderCategoryToken::Comment(comment)));
+        for w in watched.iter_mut().filter(|w| changed.contains(&w.path)) {
+            // A file that's missing is in the middle of being replaced
+            let contents = fs::read(&w.path).ok();
+            if contents.is_none() || contents == w.contents {
+                continue;
+            }
+            report(w.file_path, w.output, settings, color);
+            w.contents = fs::read(&w.path).ok();
+        }
     }
+    Ok(())
+}
 
-    if let Some(category) = parse_trader_category(chars)? {
-        return Ok(Some(TraderCategoryToken::TraderCategory(category)));
+fn report(file_path: &str, output: &Output, settings: &Settings, color: bool) {
+    let mut warnings = Vec::new();
+    let result = match (work(file_path, output, settings, &mut warnings), settings.mode) {
+        (Ok(true), Mode::Check | Mode::Diff | Mode::DryRun) => paint("would change", YELLOW, color),
+        (Ok(_), Mode::Format) => "formatted".to_string(),
+        (Ok(_), _) => "ok".to_string(),
+        (Err(err), _) => paint(&err.to_string(), RED, color),
+    };
+    stderr().write_all(format!("[{}] {}: {}\n", timestamp(), file_path, result).as_bytes()).unwrap();
+    for warning in warnings {
+        stderr().write_all(format!("    {}\n", paint(&warning.to_string(), YELLOW, color)).as_bytes()).unwrap();
     }
-
-    Ok(None)
-
 }
 
-fn parse_trader(chars: &mut Peekable<Chars>) -> Result<Option<Trader>, String> {
-    
-    consume_spaces(chars)?;
-
-    let c0 = chars.peek();
+// The time of day in UTC, watching is meant for a terminal left open next to the editor
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    format!("{:02}:{:02}:{:02}", secs / 3600 % 24, secs / 60 % 60, secs % 60)
+}
 
-    if Some(&'<') != c0 {
-        return Ok(None);
-    }
+#[derive(Debug, Serialize)]
+struct TraderListing<'a> {
+    name: &'a str,
+    categories: Vec<&'a str>,
+}
 
-    let mut txt: String = String::new();
+fn trader_listings(document: &Document) -> Vec<TraderListing<'_>> {
+    document.traders()
+        .map(|t| TraderListing {
+            name: &t.name.text,
+            categories: t.categories.iter()
+                .filter_map(|c| match c {
+                    TraderCategoryToken::TraderCategory(category) => Some(category.name.text.as_str()),
+                    TraderCategoryToken::Comment(_) => None,
+                })
+                .collect(),
+        })
+        .collect()
+}
 
-    let mut internal_idx = 0;
-    let mut ichars = chars.clone();
-    ichars.next();
-    for c in ichars {
-        match c {
-            '>' | '/' => break,
-            '\n' | '\r' => return Err(format!("Error parsing trader name, unclosed tag")),
-            c => txt.push(c)
+fn list_traders(document: &Document, categories: bool, line_ending: LineEnding) -> String {
+    let mut out = String::new();
+    for listing in trader_listings(document) {
+        out.push_str(&format!("{}{}", listing.name, line_ending));
+        if categories {
+            for category in listing.categories {
+                out.push_str(&format!("    {}{}", category, line_ending));
+            }
         }
-        internal_idx = internal_idx + 1;
     }
+    out
+}
 
-    if txt != "Trader" {
-        return Ok(None)
+// One class per line, the number of listings lined up after the names
+fn count_duplicates(document: &Document, top: usize, line_ending: LineEnding) -> String {
+    let top: Vec<Duplicate> = duplicates(document).into_iter().take(top).collect();
+    let width = top.iter().map(|d| d.class.chars().count()).max().unwrap_or_default();
+    let mut out = String::new();
+    for duplicate in top {
+        let listings: Vec<String> = duplicate.listings.iter()
+            .map(|l| format!("{} > {} ({})", l.trader, l.category, l.location))
+            .collect();
+        let count = duplicate.listings.len();
+        out.push_str(&format!("{:<width$}{:>6}  {}{}", duplicate.class, count, listings.join(", "), line_ending));
     }
+    out
+}
 
-    chars.advance_by(internal_idx + 2).map_err(|_| {
-        format!("Error advancing index parsing trader name")
-    })?;
-
-    let line = parse_line(chars)?;
-
-
+#[derive(Debug, Serialize)]
+struct ExtractedComment<'a> {
+    line: usize,
+    column: usize,
+    text: &'a str,
+}
 
-    let mut categories = Vec::new();
-    while let Some(currency) = parse_trader_category_token(chars)? {
-        categories.push(currency);
+impl<'a> From<&'a Comment> for ExtractedComment<'a> {
+    fn from(comment: &'a Comment) -> Self {
+        ExtractedComment { line: comment.location.line, column: comment.location.column, text: comment.text.trim() }
     }
-
-
-
-    Ok(Some(Trader {
-        name: line,
-        categories
-    }))
-
-
 }
 
-fn parse_comment(chars: &mut Peekable<Chars>) -> Result<Option<Comment>, String> {
-    consume_spaces(chars)?;
-
-    let c0 = chars.peek();
-    
-    if Some(&'/') != c0 {
-        let mut further = chars.clone();
-        further.next();
-        let c1 = further.peek();
-        if Some(&'/') != c1 {
-            return Ok(None)
+// Only the text of each comment, block comments spanning several lines are printed as they are
+fn extract_comments(document: &Document, locations: bool, line_ending: LineEnding) -> String {
+    let mut out = String::new();
+    for comment in document.comments().map(ExtractedComment::from) {
+        if locations {
+            out.push_str(&format!("{}:{}: ", comment.line, comment.column));
         }
+        out.push_str(&format!("{}{}", comment.text, line_ending));
     }
+    out
+}
 
-    chars.next();
-    chars.next();
-
-    let mut msg: String = String::new();
-    while let Some(c) = chars.peek() {
-        match c {
-            '\n' | '\r' => {
-                msg = msg.trim().into();
-                break
-            },
-            s => msg.push(*s)
-        }
-        chars.next();
+fn parse_input(contents: &str, settings: &Settings, warnings: &mut Vec<Violation>) -> Result<Document, ParseError> {
+    match (settings.input_format, settings.all_errors) {
+        (InputFormat::Text, false) => {
+            let (parsed, found) = parse_with_warnings(contents, &settings.parse)?;
+            warnings.extend(found.into_iter().map(parse_warning));
+            Ok(parsed)
+        },
+        (InputFormat::Text, true) => parse_all_with(contents, &settings.parse).map_err(collected),
+        (InputFormat::Json, _) => from_json(contents),
     }
+}
 
-    Ok(Some(Comment(msg)))
-
+// Lines skipped with --recover come back as the error that made them fail
+fn parse_warning(err: ParseError) -> Violation {
+    let message = match err {
+        ParseError::NestedBlock { .. } => err.to_string(),
+        err => format!("skipped a line that failed to parse: {}", err),
+    };
+    Violation { trader: None, category: None, class: None, message }
 }
 
-fn parse_line(chars: &mut Peekable<Chars>) -> Result<Line, String> {
-    consume_only_spaces(chars)?;
-    let mut text: String = String::new();
-    let mut comment: Option<Comment> = None;
-    while let Some(c) = chars.peek() {
-        match c {
-            '\n' | '\r' => {
-                text = text.trim().into();
-                chars.next();
-                break
-            },
-            '/' => {
-                comment = parse_comment(chars)?;
-                if comment.is_some() {
-                    text = text.trim().into();
-                    break;
-                }
-            },
-            c => text.push(*c)
-        };
-        chars.next();
+fn collected(mut errors: Vec<ParseError>) -> ParseError {
+    if errors.len() == 1 {
+        errors.remove(0)
+    } else {
+        ParseError::Errors(errors)
     }
-
-    Ok(Line{ text, comment })
 }
 
-fn parse_csv_line(chars: &mut Peekable<Chars>) -> Result<Option<CSVLine>, String> {
-    consume_only_spaces(chars)?;
-    let mut values: Vec<String> = Vec::new();
-    let mut value: String = String::new();
-    let mut comment: Option<Comment> = None;
-
-    while let Some(c) = chars.peek() {
-        match c {
-            '<' => return Ok(None),
-            '\n' | '\r' => {
-                value = value.trim().into();
-                if value.len() > 0 {
-                    values.push(value);
-                }
-                chars.next();
-                break;
-            },
-            ',' => {
-                value = value.trim().into();
-                if value.len() > 0 {
-                    values.push(value);
-                }
-                value = String::new();
-                chars.next();
-            },
-            '/' => {
-                comment = parse_comment(chars)?;
-                if comment.is_some() {
-                    value = value.trim().into();
-                    if value.len() > 0 {
-                        values.push(value);
-                    }
-                    break;
-                }
-
-            },
-            c => {
-                value.push(*c);
-                chars.next();
-            }
-        };
-    }
+// Compared byte for byte, a missing or extra line break at the end is rewritten like anything else
+fn is_changed(original: &str, formatted: &str) -> bool {
+    original != formatted
+}
 
-    if values.is_empty() {
-        return Ok(None)
-    } else {
-        Ok(Some(CSVLine { values, comment }))
+// Each token is written as soon as it's parsed. Files are written to a temporary file next to the
+// destination first, so a parse error halfway through doesn't leave a truncated config behind.
+// Stdout gets the tokens before the error, --output is the way to get all or nothing.
+fn stream(
+    tokens: &mut Tokens,
+    file_path: &str,
+    output: &Output,
+    opts: &FormatOptions,
+    backup: Option<&str>,
+) -> Result<(), ParseError> {
+    match output {
+        Output::Stdout => write_tokens(tokens, &mut BufWriter::new(stdout().lock()), opts),
+        Output::InPlace => stream_file(tokens, file_path, opts, is_gzip_file(file_path), backup),
+        Output::File(output_file_path) => {
+            stream_file(tokens, output_file_path, opts, is_gzip_path(output_file_path), None)
+        },
     }
+}
 
+fn stream_file(
+    tokens: &mut Tokens,
+    file_path: &str,
+    opts: &FormatOptions,
+    compress: bool,
+    backup: Option<&str>,
+) -> Result<(), ParseError> {
+    replace_file(file_path, backup, |tmp| {
+        if compress {
+            let mut encoder = GzEncoder::new(BufWriter::new(tmp), Compression::default());
+            write_tokens(tokens, &mut encoder, opts)?;
+            encoder.finish().map(|_| ()).map_err(|err| ParseError::Io { action: "Error writing output", source: err })
+        } else {
+            write_tokens(tokens, &mut BufWriter::new(tmp), opts)
+        }
+    })
 }
 
-fn parse_currency(chars: &mut Peekable<Chars>) -> Result<Option<CSVLine>, String> {
-    consume_spaces(chars)?;
+// The destination is only replaced once write succeeded on a temporary file, a failed write leaves
+// the file as it was. The backup is taken right before that too, so a file that fails to parse
+// doesn't get one, and neither is done when the file already holds what was written.
+fn replace_file(
+    file_path: &str,
+    backup_suffix: Option<&str>,
+    write: impl FnOnce(fs::File) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    create_parent(Path::new(file_path))?;
+    let (tmp_path, tmp) = create_temp(file_path)?;
+
+    let written = write(tmp).and_then(|_| {
+        if same_contents(file_path, &tmp_path) {
+            return Ok(false);
+        }
+        if let Some(suffix) = backup_suffix {
+            backup(file_path, suffix)?;
+        }
+        Ok(true)
+    });
+    match written {
+        Ok(true) => fs::rename(&tmp_path, file_path).map_err(|err| {
+            ParseError::Io { action: "Error replacing destination file", source: err }
+        }),
+        Ok(false) => {
+            let _ = fs::remove_file(&tmp_path);
+            Ok(())
+        },
+        Err(err) => {
+            let _ = fs::remove_file(&tmp_path);
+            Err(err)
+        },
+    }
+}
 
-    let c0 = chars.peek();
+// Both files hold the same config, compressed or not alike
+fn same_contents(file_path: &str, other_path: &str) -> bool {
+    let read = |p: &str| {
+        let bytes = fs::read(p).ok()?;
+        Some((bytes.starts_with(&GZIP_MAGIC), read_maybe_gzip(bytes).ok()?))
+    };
+    read(file_path).is_some_and(|contents| read(other_path) == Some(contents))
+}
 
-    if Some(&'<') != c0 {
-        return Ok(None);
+// The temporary file is created next to the destination so the rename stays on one file system,
+// it's named after this run and never replaces an existing file
+fn create_temp(file_path: &str) -> Result<(String, fs::File), ParseError> {
+    let mut counter = 0;
+    loop {
+        let tmp_path = format!("{}.{}.{}.tmp", file_path, process::id(), counter);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&tmp_path) {
+            Ok(tmp) => return Ok((tmp_path, tmp)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+            Err(err) => return Err(ParseError::Io { action: "Error creating temporary file", source: err }),
+        }
     }
+}
 
-    let mut txt: String = String::new();
-    let mut internal_idx = 0;
-    let mut ichars = chars.clone();
-    ichars.next();
-    for c in ichars {
-        match c {
-            '>' | '/' => break,
-            '\n' | '\r' => return Err(format!("Error parsing curency name, unexpected new line")),
-            c => txt.push(c)
+fn write_tokens<W: Write>(tokens: &mut Tokens, w: &mut W, opts: &FormatOptions) -> Result<(), ParseError> {
+    let write_error = |err| ParseError::Io { action: "Error writing output", source: err };
+    let mut previous = None;
+    for t in tokens {
+        let t = t?;
+        write_token(&t, previous.as_ref(), w, opts).map_err(write_error)?;
+        if !matches!(t, Token::BlankLine) {
+            previous = Some(t);
         }
-        internal_idx = internal_idx + 1;
     }
+    w.flush().map_err(write_error)
+}
 
-    if txt != "Currency" {
-        return Ok(None)
+// Existing backups are never overwritten, a counter is appended instead
+fn backup(file_path: &str, suffix: &str) -> Result<(), ParseError> {
+    let mut backup_path = format!("{}{}", file_path, suffix);
+    let mut counter = 1;
+    while Path::new(&backup_path).exists() {
+        backup_path = format!("{}{}.{}", file_path, suffix, counter);
+        counter += 1;
     }
-
-    chars.advance_by(internal_idx + 2).map_err(|_| {
-        format!("Error advancing index parsing currency")
+    fs::copy(file_path, &backup_path).map_err(|err| {
+        ParseError::Io { action: "Error backing up file", source: err }
     })?;
-
-    let line = parse_csv_line(chars)?;
-
-    Ok(line)
+    Ok(())
 }
 
-fn parse_currency_token(chars: &mut Peekable<Chars>) -> Result<Option<CurrencyToken>, String> {
-
-
-    if let Some(comment)  = parse_comment(chars)? {
-        return Ok(Some(CurrencyToken::Comment(comment)));
-    }
-
-    if let Some(currency) = parse_currency(chars)? {
-        return Ok(Some(CurrencyToken::Currency(currency)));
+fn create_parent(p: &Path) -> Result<(), ParseError> {
+    if let Some(parent) = p.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            ParseError::Io { action: "Error creating parent directory of destination file", source: err }
+        })?;
     }
-
-    Ok(None)
-
+    Ok(())
 }
 
-fn parse_currency_name(chars: &mut Peekable<Chars>) -> Result<Option<CurrencyName>, String> {
-    consume_spaces(chars)?;
-
-    let c0 = chars.peek();
+fn write_file(file_path: &str, content: &str, compress: bool, backup: Option<&str>) -> Result<(), ParseError> {
+    replace_file(file_path, backup, |mut tmp| {
+        let written = if compress {
+            gzip(content.as_bytes()).and_then(|compressed| tmp.write_all(&compressed))
+        } else {
+            tmp.write_all(content.as_bytes())
+        };
+        written.map_err(|err| {
+            ParseError::Io { action: "Error writing file", source: err }
+        })
+    })
+}
 
-    if Some(&'<') != c0 {
-        return Ok(None);
-    }
+fn gzip(content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
 
-    let mut txt: String = String::new();
+// Archived configs are often kept as .txt.gz, new files with such a name are written compressed
+fn is_gzip_path(file_path: &str) -> bool {
+    Path::new(file_path).extension().is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+}
 
-    let mut internal_idx = 0;
-    let mut ichars = chars.clone();
-    ichars.next();
-    for c in ichars {
-        match c {
-            '>' | '/' => break,
-            '\n' | '\r' => return Err(format!("Error parsing curency name, unclosed tag")),
-            c => txt.push(c)
-        }
-        internal_idx = internal_idx + 1;
-    }
+// Files read compressed are written back compressed, whatever they're named, as read_file goes by
+// the magic bytes too
+fn is_gzip_file(file_path: &str) -> bool {
+    let mut magic = [0; 2];
+    fs::File::open(file_path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == GZIP_MAGIC
+}
 
-    if txt != "CurrencyName" {
-        return Ok(None)
+fn read_file(file_path: &str) -> Result<String, ParseError> {
+    if file_path == STDIN_PATH {
+        return read_from(stdin().lock());
     }
 
-    chars.advance_by(internal_idx + 2).map_err(|_| {
-        format!("Error advancing index parsing currency name")
-    })?;
-
-    let line = parse_line(chars)?;
-
-    let mut currencies = Vec::new();
-    while let Some(currency) = parse_currency_token(chars)? {
-        currencies.push(currency);
+    let p = Path::new(file_path);
+    let invalid = |reason| ParseError::InvalidPath { path: file_path.to_string(), reason };
+    match fs::metadata(p) {
+        Ok(metadata) if metadata.is_dir() => return Err(invalid("Path is a directory, not a file")),
+        Ok(metadata) if !metadata.is_file() => return Err(invalid("Path is not a regular file")),
+        Ok(_) => (),
+        Err(err) => return Err(read_error(file_path, err)),
     }
-
-    Ok(Some(CurrencyName {
-        name: line,
-        currencies
-    }))
-
+    read_maybe_gzip(fs::read(p).map_err(|err| read_error(file_path, err))?)
 }
 
-fn consume_spaces(chars: &mut Peekable<Chars>) -> Result<(), String> {
-    while let Some(c) = chars.peek() {
-        match c {
-            ' ' | '\t' | '\n' | '\r' => (),
-            _ => break,
-        }
-        chars.next();
-    }
-    Ok(())
+// The common reasons a file can't be read get a message of their own, the rest keep the one of
+// the error
+fn read_error(file_path: &str, err: io::Error) -> ParseError {
+    let reason = match err.kind() {
+        io::ErrorKind::NotFound => "File not found",
+        io::ErrorKind::PermissionDenied => "Permission denied reading the file",
+        _ => return ParseError::Io { action: "Error reading file", source: err },
+    };
+    ParseError::InvalidPath { path: file_path.to_string(), reason }
 }
 
-fn consume_only_spaces(chars: &mut Peekable<Chars>) -> Result<(), String> {
-    while let Some(c) = chars.peek() {
-        match c {
-            ' ' | '\t' | '\n' => (),
-            _ => break,
-        }
-        chars.next();
-    }
-    Ok(())
-}
\ No newline at end of file
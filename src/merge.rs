@@ -0,0 +1,77 @@
+use crate::token::*;
+use crate::validate::Violation;
+
+// Traders and categories missing from tokens are appended whole, comments included. Within a
+// category present in both only the items whose class isn't listed yet are appended, a class
+// listed in both with different values is reported and tokens keeps its own values.
+pub fn merge(tokens: &mut Vec<Token>, other: Vec<Token>) -> Vec<Violation> {
+    let mut conflicts = Vec::new();
+    for t in other.into_iter() {
+        if let Token::Trader(trader) = t {
+            let existing = tokens.iter_mut().find_map(|t| match t {
+                Token::Trader(existing) if existing.name.text == trader.name.text => Some(existing),
+                _ => None,
+            });
+            match existing {
+                Some(existing) => merge_trader(existing, trader, &mut conflicts),
+                None => {
+                    // New traders go before the FileEnd tag so it stays the last one
+                    let end = tokens.iter().position(|t| matches!(t, Token::FileEnd(_))).unwrap_or(tokens.len());
+                    tokens.insert(end, Token::Trader(trader));
+                },
+            }
+        }
+    }
+    conflicts
+}
+
+fn merge_trader(trader: &mut Trader, other: Trader, conflicts: &mut Vec<Violation>) {
+    for c in other.categories.into_iter() {
+        if let TraderCategoryToken::TraderCategory(category) = c {
+            let existing = trader.categories.iter_mut().find_map(|c| match c {
+                TraderCategoryToken::TraderCategory(existing) if existing.name.text == category.name.text => Some(existing),
+                _ => None,
+            });
+            match existing {
+                Some(existing) => merge_category(&trader.name.text, existing, category, conflicts),
+                None => trader.categories.push(TraderCategoryToken::TraderCategory(category)),
+            }
+        }
+    }
+}
+
+fn merge_category(trader: &str, category: &mut TraderCategory, other: TraderCategory, conflicts: &mut Vec<Violation>) {
+    // Appended after the last item, comments following it usually introduce the next category
+    let mut end = category.items.iter()
+        .rposition(|i| matches!(i, CategoryItemToken::CategoryItem(_)))
+        .map_or(0, |i| i + 1);
+    for i in other.items.into_iter() {
+        if let CategoryItemToken::CategoryItem(item) = i {
+            let existing = category.items.iter().find_map(|i| match i {
+                CategoryItemToken::CategoryItem(existing) if existing.class == item.class => Some(existing),
+                _ => None,
+            });
+            match existing {
+                Some(existing) if !same_values(existing, &item) => conflicts.push(Violation {
+                    trader: Some(trader.to_string()),
+                    category: Some(category.name.text.clone()),
+                    class: Some(item.class.clone()),
+                    message: format!(
+                        "listed as {}, {}, {} at {} and as {}, {}, {} in the merged file at {}",
+                        existing.amount, existing.buy_value, existing.sell_value, existing.location,
+                        item.amount, item.buy_value, item.sell_value, item.location
+                    ),
+                }),
+                Some(_) => (),
+                None => {
+                    category.items.insert(end, CategoryItemToken::CategoryItem(item));
+                    end += 1;
+                },
+            }
+        }
+    }
+}
+
+fn same_values(a: &CategoryItem, b: &CategoryItem) -> bool {
+    a.amount == b.amount && a.buy_value == b.buy_value && a.sell_value == b.sell_value && a.stock == b.stock
+}
@@ -0,0 +1,118 @@
+use crate::items::items;
+use crate::token::*;
+use crate::validate::{Violation, NOT_TRADEABLE};
+
+enum Price {
+    Normalized(String),
+    // Mixes . and , or has a single separator before three digits, so it could be a decimal
+    Ambiguous,
+    Invalid,
+}
+
+// Writes buy and sell values the same way everywhere, -1.0 or - 1 become -1, 0100 or 100.0 become
+// 100 and thousands separators as in 1.000.000 are dropped. Values that still aren't a whole
+// number or -1 are reported and left as they are, ambiguous ones are left for ambiguous_prices.
+// 0, 00, 0.0 or -0 are written by admins for the -1 sentinel and become -1 as well.
+// Percentages keep their % with the number before it normalized, 050 % becomes 50%.
+pub fn normalize_prices(tokens: &mut [Token]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for t in tokens.iter_mut() {
+        if let Token::Trader(trader) = t {
+            for c in trader.categories.iter_mut() {
+                if let TraderCategoryToken::TraderCategory(category) = c {
+                    for i in category.items.iter_mut() {
+                        if let CategoryItemToken::CategoryItem(item) = i {
+                            let prices = [("buy value", &mut item.buy_value), ("sell value", &mut item.sell_value)];
+                            for (name, value) in prices {
+                                match normalize_price(value) {
+                                    Price::Normalized(normalized) => *value = normalized,
+                                    Price::Ambiguous => (),
+                                    Price::Invalid => violations.push(Violation {
+                                        trader: Some(trader.name.text.clone()),
+                                        category: Some(category.name.text.clone()),
+                                        class: Some(item.class.clone()),
+                                        message: format!("{} \"{}\" is not a whole number, a percentage or -1", name, value),
+                                    }),
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    violations
+}
+
+// Buy and sell values normalize_prices leaves alone because they could be read as decimals or as
+// thousands, meant to be reported as warnings
+pub fn ambiguous_prices(tokens: &[Token]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (trader, category, item) in items(tokens) {
+        for (name, value) in [("buy value", &item.buy_value), ("sell value", &item.sell_value)] {
+            if let Price::Ambiguous = normalize_price(value) {
+                violations.push(Violation {
+                    trader: Some(trader.name.text.clone()),
+                    category: Some(category.name.text.clone()),
+                    class: Some(item.class.clone()),
+                    message: format!("{} \"{}\" could be a decimal or a number with thousands separators", name, value),
+                });
+            }
+        }
+    }
+    violations
+}
+
+fn normalize_price(value: &str) -> Price {
+    match normalize_number(value) {
+        Price::Normalized(n) if n == "0" => Price::Normalized(NOT_TRADEABLE.to_string()),
+        price => price,
+    }
+}
+
+// Like normalize_price but keeps 0, a 0% percentage is a price of its own
+fn normalize_number(value: &str) -> Price {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if let Some(number) = compact.strip_suffix('%') {
+        return match normalize_number(number) {
+            Price::Normalized(n) if n != NOT_TRADEABLE => Price::Normalized(format!("{}%", n)),
+            Price::Normalized(_) | Price::Invalid => Price::Invalid,
+            Price::Ambiguous => Price::Ambiguous,
+        };
+    }
+    if let Ok(price) = compact.parse::<u64>() {
+        return Price::Normalized(price.to_string());
+    }
+    if let Some(digits) = without_thousands_separators(&compact) {
+        // 1.500 is as much 1500 as 1.5 written with three decimals, only more groups settle it
+        return match compact.matches(['.', ',']).count() {
+            1 => Price::Ambiguous,
+            _ => Price::Normalized(digits),
+        };
+    }
+    if compact.contains('.') && compact.contains(',') {
+        return Price::Ambiguous;
+    }
+
+    match compact.parse::<f64>() {
+        Ok(-1.0) => Price::Normalized(NOT_TRADEABLE.to_string()),
+        Ok(price) if price >= 0.0 && price.fract() == 0.0 && price <= u64::MAX as f64 => {
+            Price::Normalized((price as u64).to_string())
+        },
+        _ => Price::Invalid,
+    }
+}
+
+// 1.000, 1,000 or 12.500.000 written in some locales, DayZ reads them as 1, 1 and 12. Groups after
+// the first have to be three digits and the first can't start with 0, 0.500 is a decimal.
+pub(crate) fn without_thousands_separators(value: &str) -> Option<String> {
+    let separator = value.chars().find(|c| *c == '.' || *c == ',')?;
+    let groups: Vec<&str> = value.split(separator).collect();
+    let first = groups[0];
+    let is_digits = |g: &str| !g.is_empty() && g.bytes().all(|b| b.is_ascii_digit());
+    let valid = is_digits(first)
+        && first.len() <= 3
+        && !first.starts_with('0')
+        && groups[1..].iter().all(|g| g.len() == 3 && is_digits(g));
+    valid.then(|| groups.concat())
+}
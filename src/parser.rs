@@ -0,0 +1,847 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::{Location, ParseError};
+use crate::items::items;
+use crate::token::*;
+
+const BOM: char = '\u{FEFF}';
+const TOP_LEVEL_TAGS: [&str; 4] = ["CurrencyName", "Trader", "OpenFile", "FileEnd"];
+const TAGS: [&str; 6] = ["CurrencyName", "Currency", "Trader", "Category", "OpenFile", "FileEnd"];
+
+// Reads straight from the remaining input so tags can be looked at without cloning an iterator
+#[derive(Debug, Clone)]
+pub(crate) struct Source<'a> {
+    rest: &'a str,
+    location: Location,
+    // Line of the last character consumed that wasn't whitespace
+    content_line: usize,
+    // Only whitespace was consumed since the last line break
+    line_start: bool,
+    // Separates the values of CSV lines
+    delimiter: char,
+    raw_comments: bool,
+    // <OpenFile> lines are kept exactly as written after the tag
+    raw_headers: bool,
+    // Tag names are matched ignoring case and read with the casing of the known tag
+    lenient_tags: bool,
+    // Broken item lines are kept as comments and their errors collected instead of returned, the
+    // errors are shared with the clones made to look ahead
+    recover: bool,
+    skipped: Rc<RefCell<Vec<ParseError>>>,
+    // Problems that don't stop parsing, shared with clones the same way
+    warnings: Rc<RefCell<Vec<ParseError>>>,
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(contents: &'a str) -> Self {
+        Source {
+            rest: contents,
+            location: Location { line: 1, column: 1, offset: 0 },
+            content_line: 1,
+            line_start: true,
+            delimiter: ',',
+            raw_comments: false,
+            raw_headers: false,
+            lenient_tags: false,
+            recover: false,
+            skipped: Rc::default(),
+            warnings: Rc::default(),
+        }
+    }
+
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    pub(crate) fn starts_with(&self, pattern: &str) -> bool {
+        self.rest.starts_with(pattern)
+    }
+
+    // The name of the tag at the current position, between < and > or /, without consuming it.
+    // Err with the partial name when the line ends before the tag is closed.
+    pub(crate) fn tag_name(&self) -> Option<Result<&'a str, &'a str>> {
+        let rest = self.rest.strip_prefix('<')?;
+        let end = rest.find(['>', '/', '\n', '\r']).unwrap_or(rest.len());
+        let name = &rest[..end];
+        match rest[end..].chars().next() {
+            Some('\n' | '\r') => Some(Err(name)),
+            _ if self.lenient_tags => {
+                Some(Ok(TAGS.into_iter().find(|t| t.eq_ignore_ascii_case(name)).unwrap_or(name)))
+            },
+            _ => Some(Ok(name)),
+        }
+    }
+
+    // The rest of the current line without consuming it
+    pub(crate) fn rest_of_line(&self) -> &'a str {
+        let end = self.rest.find(['\n', '\r']).unwrap_or(self.rest.len());
+        &self.rest[..end]
+    }
+
+    // Consumes the characters before the first one matching stop or the end of the line, taking
+    // them as one slice so long lines aren't copied a character at a time
+    pub(crate) fn take_until(&mut self, stop: impl Fn(char) -> bool) -> &'a str {
+        let end = self.rest.find(|c| c == '\n' || c == '\r' || stop(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        self.location.offset += end;
+        self.location.column += taken.chars().count();
+        if !taken.chars().all(char::is_whitespace) {
+            self.content_line = self.location.line;
+            self.line_start = false;
+        }
+        taken
+    }
+
+    pub(crate) fn location(&self) -> Location {
+        self.location
+    }
+
+    pub(crate) fn content_line(&self) -> usize {
+        self.content_line
+    }
+
+    pub(crate) fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub(crate) fn raw_comments(&self) -> bool {
+        self.raw_comments
+    }
+
+    pub(crate) fn at_line_start(&self) -> bool {
+        self.line_start
+    }
+}
+
+impl Iterator for Source<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let mut chars = self.rest.chars();
+        let c = chars.next()?;
+        self.rest = chars.as_str();
+        self.location.offset += c.len_utf8();
+        if c == '\n' {
+            self.location.line += 1;
+            self.location.column = 1;
+        } else {
+            self.location.column += 1;
+        }
+        if !c.is_whitespace() {
+            self.content_line = self.location.line;
+            self.line_start = false;
+        } else if c == '\n' {
+            self.line_start = true;
+        }
+        Some(c)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    // The file has to end with <FileEnd> and items can't have a stock column, as DayZ expects
+    pub strict: bool,
+    // The order the values of category items are written in
+    pub column_order: ColumnOrder,
+    // Separates the values of items and currencies
+    pub delimiter: char,
+    // Comments keep the spacing after their marker instead of being trimmed
+    pub raw_comments: bool,
+    // <OpenFile> lines keep their spacing, for headers with aligned columns
+    pub raw_headers: bool,
+    // <trader> and <CATEGORY> are read as <Trader> and <Category>
+    pub lenient_tags: bool,
+    // Broken item lines are skipped and kept as comments instead of failing the file
+    pub recover: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict: false,
+            column_order: ColumnOrder::default(),
+            delimiter: ',',
+            raw_comments: false,
+            raw_headers: false,
+            lenient_tags: false,
+            recover: false,
+        }
+    }
+}
+
+// Parses one token at a time so the formatted output can be written while the rest of the file
+// is still being parsed. Iteration stops after the first error unless it resyncs.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    chars: Source<'a>,
+    started: bool,
+    pending: Option<Token>,
+    failed: bool,
+    resync: bool,
+    // <FileEnd> was read, the rest of the file is one Trailing token
+    ended: bool,
+    column_order: ColumnOrder,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(contents: &'a str) -> Self {
+        let mut chars = Source::new(contents.strip_prefix(BOM).unwrap_or(contents));
+        // Offsets count the byte order mark like any other character of the file
+        chars.location.offset = contents.len() - chars.rest.len();
+        Tokens {
+            chars,
+            started: false,
+            pending: None,
+            failed: false,
+            resync: false,
+            ended: false,
+            column_order: ColumnOrder::default(),
+        }
+    }
+
+    pub fn with_options(contents: &'a str, opts: &ParseOptions) -> Self {
+        Tokens::new(contents)
+            .column_order(opts.column_order)
+            .delimiter(opts.delimiter)
+            .raw_comments(opts.raw_comments)
+            .raw_headers(opts.raw_headers)
+            .lenient_tags(opts.lenient_tags)
+            .recover(opts.recover)
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.chars.delimiter = delimiter;
+        self
+    }
+
+    pub fn raw_comments(mut self, raw_comments: bool) -> Self {
+        self.chars.raw_comments = raw_comments;
+        self
+    }
+
+    pub fn raw_headers(mut self, raw_headers: bool) -> Self {
+        self.chars.raw_headers = raw_headers;
+        self
+    }
+
+    pub fn lenient_tags(mut self, lenient_tags: bool) -> Self {
+        self.chars.lenient_tags = lenient_tags;
+        self
+    }
+
+    // A category item that fails to parse is written as a // SKIPPED: comment with the line as it
+    // was, its error can be read with take_skipped()
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.chars.recover = recover;
+        self
+    }
+
+    // The errors of the lines skipped since the last call when recovering
+    pub fn take_skipped(&mut self) -> Vec<ParseError> {
+        self.chars.skipped.take()
+    }
+
+    // Problems found since the last call that didn't stop parsing, such as a block that looks nested
+    // in another one
+    pub fn take_warnings(&mut self) -> Vec<ParseError> {
+        self.chars.warnings.take()
+    }
+
+    pub fn column_order(mut self, column_order: ColumnOrder) -> Self {
+        self.column_order = column_order;
+        self
+    }
+
+    // After an error the rest of the broken block is skipped and parsing carries on from the next
+    // line starting with a top-level tag
+    pub fn resync_on_error(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    fn parse_next(&mut self) -> Result<Option<Token>, ParseError> {
+        let chars = &mut self.chars;
+        if self.ended {
+            return parse_trailing(chars);
+        }
+        // Tokens consume the whitespace after them, so blank lines are found by comparing where
+        // the next token starts with the last line that had any content
+        consume_spaces(chars)?;
+        let blank_line = self.started && chars.peek().is_some() && chars.location().line > chars.content_line() + 1;
+
+        let Some(mut t) = parse_token(chars)? else {
+            return Ok(None);
+        };
+        if self.column_order != ColumnOrder::default() {
+            reorder_items(&mut t, self.column_order);
+        }
+        self.started = true;
+        self.ended = matches!(t, Token::FileEnd(_));
+        if blank_line {
+            self.pending = Some(t);
+            return Ok(Some(Token::BlankLine));
+        }
+        Ok(Some(t))
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(t) = self.pending.take() {
+            return Some(Ok(t));
+        }
+        if self.failed {
+            return None;
+        }
+        let start = self.chars.location();
+        match self.parse_next() {
+            Ok(t) => t.map(Ok),
+            Err(err) => {
+                if self.resync {
+                    resync(&mut self.chars, start, err.location());
+                } else {
+                    self.failed = true;
+                }
+                Some(Err(err))
+            },
+        }
+    }
+}
+
+fn resync(chars: &mut Source, start: Location, failed_at: Option<Location>) {
+    // A token that failed without consuming anything would fail again in the same place
+    let mut skip_line = chars.location() == start;
+    loop {
+        if skip_line {
+            while chars.next().is_some_and(|c| c != '\n') {}
+        }
+        skip_line = true;
+
+        let mut probe = chars.clone();
+        let _ = consume_spaces(&mut probe);
+        match probe.tag_name() {
+            Some(Ok(name)) if TOP_LEVEL_TAGS.contains(&name) => return,
+            // An unclosed tag is an error of its own, skipping it with the broken block would hide it
+            Some(Err(_)) if Some(probe.location()) != failed_at => return,
+            _ if probe.peek().is_none() => {
+                *chars = probe;
+                return;
+            },
+            _ => (),
+        }
+    }
+}
+
+fn reorder_items(token: &mut Token, column_order: ColumnOrder) {
+    if let Token::Trader(trader) = token {
+        for c in trader.categories.iter_mut() {
+            if let TraderCategoryToken::TraderCategory(category) = c {
+                for i in category.items.iter_mut() {
+                    if let CategoryItemToken::CategoryItem(item) = i {
+                        item.reorder(column_order);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Like process_file but the warnings found along the way are returned with the tokens, along with
+// the errors of the lines skipped when recovering
+pub(crate) fn process_file_warning(
+    contents: &str,
+    opts: &ParseOptions,
+) -> Result<(Vec<Token>, Vec<ParseError>), ParseError> {
+    let mut tokens = Tokens::with_options(contents, opts);
+    let parsed: Vec<Token> = tokens.by_ref().collect::<Result<_, _>>()?;
+    if opts.strict {
+        check_strict(&parsed)?;
+    }
+    let mut warnings = tokens.take_skipped();
+    warnings.extend(tokens.take_warnings());
+    Ok((parsed, warnings))
+}
+
+pub(crate) fn process_file(contents: &str, opts: &ParseOptions) -> Result<Vec<Token>, ParseError> {
+    let tokens: Vec<Token> = Tokens::with_options(contents, opts).collect::<Result<_, _>>()?;
+    if opts.strict {
+        check_strict(&tokens)?;
+    }
+    Ok(tokens)
+}
+
+// Like process_file but parsing resyncs after each error so all of them are returned
+pub(crate) fn process_file_all(contents: &str, opts: &ParseOptions) -> Result<Vec<Token>, Vec<ParseError>> {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for t in Tokens::with_options(contents, opts).resync_on_error() {
+        match t {
+            Ok(t) => tokens.push(t),
+            Err(err) => errors.push(err),
+        }
+    }
+    if opts.strict {
+        if let Err(err) = check_strict(&tokens) {
+            errors.push(err);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_strict(tokens: &[Token]) -> Result<(), ParseError> {
+    if let Some(Token::Trailing(t)) = tokens.iter().find(|t| matches!(t, Token::Trailing(_))) {
+        return Err(ParseError::ContentAfterFileEnd { location: t.location });
+    }
+
+    // Vanilla traders only read four values, the stock column of modded ones isn't allowed
+    if let Some((_, _, item)) = items(tokens).find(|(_, _, item)| item.stock.is_some()) {
+        let values = [&item.class, &item.amount, &item.buy_value, &item.sell_value].into_iter().chain(item.stock.as_ref());
+        return Err(ParseError::StockColumn {
+            line: values.map(String::as_str).collect::<Vec<_>>().join(", "),
+            location: item.location,
+        });
+    }
+
+    match tokens.last() {
+        Some(Token::FileEnd(_)) => Ok(()),
+        Some(_) => Err(ParseError::MissingFileEnd),
+        None => Err(ParseError::EmptyFile),
+    }
+}
+
+fn parse_token(chars: &mut Source) -> Result<Option<Token>, ParseError> {
+    consume_spaces(chars)?;
+    if let Some(c) = parse_comment(chars)? {
+        return Ok(Some(Token::Comment(c)));
+    }
+
+    let location = chars.location();
+    match read_tag(chars)? {
+        None if chars.peek().is_none() => Ok(None),
+        None => stray_line(chars).map(|c| Some(Token::Comment(c))),
+        Some("CurrencyName") => Ok(Some(Token::CurrencyName(parse_currency_name(chars, location)?))),
+        Some("Trader") => Ok(Some(Token::Trader(parse_trader(chars)?))),
+        Some("OpenFile") => Ok(Some(Token::OpenFile(parse_open_file(chars)?))),
+        Some("FileEnd") => Ok(Some(Token::FileEnd(parse_file_end(chars)?))),
+        // Only reached when the tag isn't part of the block before it
+        Some("Category") => Err(ParseError::MisplacedTag { name: "Category", parent: "Trader", location }),
+        Some("Currency") => Err(ParseError::MisplacedTag { name: "Currency", parent: "CurrencyName", location }),
+        Some(name) => Err(ParseError::UnknownTag { name: name.to_string(), location }),
+    }
+}
+
+fn parse_trailing(chars: &mut Source) -> Result<Option<Token>, ParseError> {
+    consume_spaces(chars)?;
+    if chars.peek().is_none() {
+        return Ok(None);
+    }
+
+    let location = chars.location();
+    let text = chars.rest.trim_end().to_string();
+    while chars.next().is_some() {}
+    Ok(Some(Token::Trailing(Trailing { text, location })))
+}
+
+fn parse_file_end(chars: &mut Source) -> Result<FileEnd, ParseError> {
+    let line = parse_line(chars)?;
+
+    Ok(FileEnd(line))
+}
+
+fn parse_open_file(chars: &mut Source) -> Result<OpenFile, ParseError> {
+    let raw = chars.raw_headers.then(|| chars.rest_of_line().trim_end().to_string());
+    let line = parse_line(chars)?;
+
+    // A block comment running past the end of the line can't be kept as part of it
+    let multiline = line.comments().any(|c| c.style == CommentStyle::Block && c.text.contains('\n'));
+    Ok(OpenFile(Line { raw: raw.filter(|_| !multiline), ..line }))
+}
+
+fn parse_trader_category_item_token(chars: &mut Source) -> Result<Option<CategoryItemToken>, ParseError> {
+    consume_spaces(chars)?;
+
+    if let Some(comment) = parse_comment(chars)? {
+        return Ok(Some(CategoryItemToken::Comment(comment)));
+    }
+
+    // The line as written is echoed when it doesn't have the right number of values
+    let raw = chars.rest_of_line();
+    if let Some(item) = parse_csv_line(chars)? {
+        let item = CategoryItem::try_from(&item).map_err(|err| match err {
+            ParseError::MissingCsvValues { found, location, .. } => {
+                ParseError::MissingCsvValues { found, line: raw.trim().to_string(), location }
+            },
+            err => err,
+        })?;
+        return Ok(Some(CategoryItemToken::CategoryItem(item)));
+    }
+
+    Ok(None)
+}
+
+fn parse_trader_category(chars: &mut Source) -> Result<TraderCategory, ParseError> {
+    let line = parse_line(chars)?;
+
+    let mut items = Vec::new();
+    loop {
+        let before = chars.recover.then(|| chars.clone());
+        match (parse_trader_category_item_token(chars), before) {
+            (Ok(Some(item)), _) => items.push(item),
+            (Ok(None), _) => break,
+            (Err(err), Some(before)) => {
+                *chars = before;
+                items.push(CategoryItemToken::Comment(skip_line(chars, err)));
+            },
+            (Err(err), None) => return Err(err),
+        }
+    }
+
+    Ok(TraderCategory {
+        name: line,
+        items
+    })
+}
+
+fn skip_line(chars: &mut Source, err: ParseError) -> Comment {
+    consume_spaces(chars).ok();
+    let location = chars.location();
+    let text = format!("SKIPPED: {}", chars.rest_of_line().trim_end());
+    while chars.next().is_some_and(|c| c != '\n') {}
+    chars.skipped.borrow_mut().push(err);
+    Comment { text, style: CommentStyle::Line, raw: false, location }
+}
+
+// A line that is neither a tag nor a comment where one is expected fails the file instead of being
+// dropped, when recovering it's kept as a // SKIPPED: comment like a broken item line
+fn stray_line(chars: &mut Source) -> Result<Comment, ParseError> {
+    consume_spaces(chars)?;
+    let err = ParseError::UnexpectedText { line: chars.rest_of_line().trim().to_string(), location: chars.location() };
+    if !chars.recover {
+        return Err(err);
+    }
+    Ok(skip_line(chars, err))
+}
+
+fn parse_trader_category_token(chars: &mut Source) -> Result<Option<TraderCategoryToken>, ParseError> {
+    consume_spaces(chars)?;
+
+    if let Some(comment) = parse_comment(chars)? {
+        return Ok(Some(TraderCategoryToken::Comment(comment)));
+    }
+
+    let before = chars.clone();
+    match read_tag(chars)? {
+        Some("Category") => Ok(Some(TraderCategoryToken::TraderCategory(parse_trader_category(chars)?))),
+        // Reported here, ending the block would report the categories after it as outside a trader
+        None if chars.peek().is_some() => stray_line(chars).map(|c| Some(TraderCategoryToken::Comment(c))),
+        _ => {
+            *chars = before;
+            Ok(None)
+        },
+    }
+}
+
+fn parse_trader(chars: &mut Source) -> Result<Trader, ParseError> {
+    let line = parse_line(chars)?;
+
+    let mut categories = Vec::new();
+    while let Some(category) = parse_trader_category_token(chars)? {
+        categories.push(category);
+    }
+
+    Ok(Trader {
+        name: line,
+        categories
+    })
+}
+
+fn parse_comment(chars: &mut Source) -> Result<Option<Comment>, ParseError> {
+    consume_spaces(chars)?;
+
+    if chars.starts_with("/*") {
+        return parse_block_comment(chars).map(Some);
+    }
+    // ; and # are only comments at the start of a line, inside values they're kept as written
+    let (style, prefix) = if chars.starts_with("//") {
+        (CommentStyle::Line, 2)
+    } else if chars.at_line_start() && chars.starts_with(";") {
+        (CommentStyle::Semicolon, 1)
+    } else if chars.at_line_start() && chars.starts_with("#") {
+        (CommentStyle::Hash, 1)
+    } else {
+        return Ok(None);
+    };
+
+    let location = chars.location();
+    for _ in 0..prefix {
+        chars.next();
+    }
+
+    // Only the comment itself is consumed, the line break is left for the caller
+    let mut msg: String = String::new();
+    while let Some(c) = chars.peek() {
+        match c {
+            '\n' | '\r' => break,
+            s => msg.push(s)
+        }
+        chars.next();
+    }
+
+    // Trailing spaces are dropped either way
+    let text = if chars.raw_comments() { msg.trim_end() } else { msg.trim() };
+    Ok(Some(Comment { text: text.into(), style, raw: chars.raw_comments(), location }))
+
+}
+
+fn parse_block_comment(chars: &mut Source) -> Result<Comment, ParseError> {
+    let start = chars.location();
+    chars.next();
+    chars.next();
+
+    let mut text: String = String::new();
+    while chars.peek().is_some() {
+        let location = chars.location();
+        match chars.next() {
+            Some('*') if Some('/') == chars.peek() => {
+                chars.next();
+                return Ok(Comment { text, style: CommentStyle::Block, raw: false, location: start });
+            },
+            Some('/') if Some('*') == chars.peek() => {
+                return Err(ParseError::NestedComment { location });
+            },
+            Some(c) => text.push(c),
+            None => break,
+        }
+    }
+
+    Err(ParseError::UnclosedComment { location: start })
+}
+
+fn parse_line(chars: &mut Source) -> Result<Line, ParseError> {
+    consume_only_spaces(chars)?;
+    let mut text: String = String::new();
+    let mut block_comments: Vec<Comment> = Vec::new();
+    let mut comment: Option<Comment> = None;
+    while let Some(c) = chars.peek() {
+        match c {
+            '\n' | '\r' => {
+                text = text.trim().into();
+                chars.next();
+                break
+            },
+            '/' => {
+                comment = parse_comment(chars)?;
+                // A block comment followed by more on the same line is kept apart from the text, so a //
+                // comment after it isn't moved to the next line
+                if comment.as_ref().is_some_and(|c| c.style == CommentStyle::Block) {
+                    consume_only_spaces(chars)?;
+                    if !matches!(chars.peek(), None | Some('\n') | Some('\r')) {
+                        block_comments.extend(comment.take());
+                        continue;
+                    }
+                }
+                if comment.is_some() {
+                    text = text.trim().into();
+                    break;
+                }
+                text.push('/');
+            },
+            _ => {
+                text.push_str(chars.take_until(|c| c == '/'));
+                continue;
+            },
+        };
+        chars.next();
+    }
+
+    Ok(Line{ text, block_comments, comment, raw: None })
+}
+
+// Values wrapped in double quotes can contain commas and //, a quote inside them is written twice
+fn parse_csv_line(chars: &mut Source) -> Result<Option<CSVLine>, ParseError> {
+    consume_only_spaces(chars)?;
+    let location = chars.location();
+    let raw = chars.rest_of_line();
+    let mut values: Vec<String> = Vec::new();
+    let mut value: String = String::new();
+    let mut comment: Option<Comment> = None;
+    let mut quoted = false;
+    // Position of the first value left empty, tabs also pad values so only other delimiters count
+    let mut empty: Option<usize> = None;
+
+    while let Some(c) = chars.peek() {
+        match c {
+            '\n' | '\r' => {
+                chars.next();
+                break;
+            },
+            '"' if quoted && chars.starts_with("\"\"") => {
+                value.push_str("\"\"");
+                chars.next();
+                chars.next();
+            },
+            '"' if quoted || value.trim().is_empty() => {
+                quoted = !quoted;
+                value.push('"');
+                chars.next();
+            },
+            c if quoted => {
+                value.push(c);
+                chars.next();
+                value.push_str(chars.take_until(|c| c == '"'));
+            },
+            // A tag further along the line ends it, the tag is parsed as the next token
+            '<' if values.is_empty() && value.trim().is_empty() => return Ok(None),
+            '<' => break,
+            c if c == chars.delimiter() => {
+                if value.trim().is_empty() && !c.is_whitespace() {
+                    empty.get_or_insert(values.len() + 1);
+                }
+                push_value(&mut values, &value);
+                value = String::new();
+                chars.next();
+            },
+            '/' => {
+                comment = parse_comment(chars)?;
+                if comment.is_some() {
+                    break;
+                }
+                value.push('/');
+                chars.next();
+            },
+            c => {
+                let delimiter = chars.delimiter();
+                value.push(c);
+                chars.next();
+                value.push_str(chars.take_until(|c| matches!(c, '"' | '<' | '/') || c == delimiter));
+            },
+        };
+    }
+    push_value(&mut values, &value);
+
+    // A delimiter after the last value is tolerated, an empty value before it would shift the others
+    // A line of nothing but delimiters is an empty value too, not the end of the block
+    if let Some(position) = empty.filter(|p| *p <= values.len() || values.is_empty()) {
+        return Err(ParseError::EmptyCsvValue { position, line: raw.trim().to_string(), location });
+    }
+
+    if values.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(CSVLine { values, comment, location }))
+    }
+
+}
+
+fn push_value(values: &mut Vec<String>, value: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+        return;
+    }
+
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => values.push(inner.replace("\"\"", "\"")),
+        None => values.push(value.to_string()),
+    }
+}
+
+fn parse_currency_token(chars: &mut Source, block: Location) -> Result<Option<CurrencyToken>, ParseError> {
+    if let Some(comment)  = parse_comment(chars)? {
+        return Ok(Some(CurrencyToken::Comment(comment)));
+    }
+
+    consume_spaces(chars)?;
+    let before = chars.clone();
+    let location = chars.location();
+    match read_tag(chars)? {
+        // An empty <Currency> would otherwise end the block and leave the ones after it stranded
+        Some("Currency") => match parse_csv_line(chars)? {
+            Some(currency) => Ok(Some(CurrencyToken::Currency(currency))),
+            None => Err(ParseError::EmptyCurrency { location }),
+        },
+        // Ends the block either way, indented deeper than the block's own tag it was probably
+        // meant to be a <Currency>
+        Some(name @ ("CurrencyName" | "Trader")) if location.column > block.column => {
+            chars.warnings.borrow_mut().push(ParseError::NestedBlock { name: name.to_string(), block, location });
+            *chars = before;
+            Ok(None)
+        },
+        None if chars.peek().is_some() => stray_line(chars).map(|c| Some(CurrencyToken::Comment(c))),
+        _ => {
+            *chars = before;
+            Ok(None)
+        },
+    }
+}
+
+fn parse_currency_name(chars: &mut Source, block: Location) -> Result<CurrencyName, ParseError> {
+    let line = parse_line(chars)?;
+
+    let mut currencies = Vec::new();
+    while let Some(currency) = parse_currency_token(chars, block)? {
+        currencies.push(currency);
+    }
+
+    Ok(CurrencyName {
+        name: line,
+        currencies
+    })
+}
+
+// Consumes a <Name> tag along with the > or / closing it and returns the name, None when the
+// next character doesn't start a tag. Callers that don't handle the tag put the source back.
+fn read_tag<'a>(chars: &mut Source<'a>) -> Result<Option<&'a str>, ParseError> {
+    let location = chars.location();
+    let name = match chars.tag_name() {
+        None => return Ok(None),
+        Some(Err(name)) => return Err(ParseError::UnclosedTag { name: name.to_string(), location }),
+        Some(Ok(name)) => name,
+    };
+
+    skip(chars, name.chars().count() + 2).map_err(|_| {
+        ParseError::UnexpectedEnd { name: name.to_string(), location }
+    })?;
+    Ok(Some(name))
+}
+
+fn skip(chars: &mut Source, n: usize) -> Result<(), usize> {
+    for i in 0..n {
+        if chars.next().is_none() {
+            return Err(n - i);
+        }
+    }
+    Ok(())
+}
+
+fn consume_spaces(chars: &mut Source) -> Result<(), ParseError> {
+    while let Some(c) = chars.peek() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' => (),
+            _ => break,
+        }
+        chars.next();
+    }
+    Ok(())
+}
+
+// Stays on the current line, so a tag without a value doesn't take the next line as its value
+// A carriage return is a line break as well, the line parsers stop at it as they do at \n
+fn consume_only_spaces(chars: &mut Source) -> Result<(), ParseError> {
+    while let Some(c) = chars.peek() {
+        match c {
+            ' ' | '\t' => (),
+            _ => break,
+        }
+        chars.next();
+    }
+    Ok(())
+}
\ No newline at end of file
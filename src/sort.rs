@@ -0,0 +1,154 @@
+use std::mem;
+
+use crate::token::*;
+
+// The parser keeps a comment between two blocks inside the block before it, so before sorting those
+// comments are lifted out and move together with the trader or category that follows them. The
+// ones after the last trader or category stay where they are.
+pub fn sort_traders(tokens: &mut Vec<Token>) {
+    let mut lifted = Vec::with_capacity(tokens.len());
+    for mut t in tokens.drain(..) {
+        let trailing = match &mut t {
+            Token::CurrencyName(c) => take_trailing(&mut c.currencies, currency_comment),
+            Token::Trader(trader) => trader_trailing(trader),
+            _ => Vec::new(),
+        };
+        lifted.push(t);
+        lifted.extend(trailing.into_iter().map(Token::Comment));
+    }
+    *tokens = lifted;
+
+    sort_attached(tokens, token_attaches, trader_name);
+}
+
+pub fn sort_categories(tokens: &mut [Token]) {
+    for t in tokens.iter_mut() {
+        if let Token::Trader(trader) = t {
+            let mut lifted = Vec::with_capacity(trader.categories.len());
+            for mut c in trader.categories.drain(..) {
+                let trailing = match &mut c {
+                    TraderCategoryToken::TraderCategory(category) => take_trailing(&mut category.items, item_comment),
+                    TraderCategoryToken::Comment(_) => Vec::new(),
+                };
+                lifted.push(c);
+                lifted.extend(trailing.into_iter().map(TraderCategoryToken::Comment));
+            }
+            trader.categories = lifted;
+
+            sort_attached(&mut trader.categories, category_attaches, category_name);
+        }
+    }
+}
+
+// Comments before an item move with it, the ones after the last item of a category stay at its end
+pub fn sort_items(tokens: &mut [Token]) {
+    for t in tokens.iter_mut() {
+        if let Token::Trader(trader) = t {
+            for c in trader.categories.iter_mut() {
+                if let TraderCategoryToken::TraderCategory(category) = c {
+                    sort_attached(&mut category.items, item_attaches, item_class);
+                }
+            }
+        }
+    }
+}
+
+fn trader_trailing(trader: &mut Trader) -> Vec<Comment> {
+    let after_categories = take_trailing(&mut trader.categories, category_comment);
+    let mut trailing = match trader.categories.last_mut() {
+        Some(TraderCategoryToken::TraderCategory(c)) => take_trailing(&mut c.items, item_comment),
+        _ => Vec::new(),
+    };
+    trailing.extend(after_categories);
+    trailing
+}
+
+fn take_trailing<T>(tokens: &mut Vec<T>, comment: fn(&T) -> Option<&Comment>) -> Vec<Comment> {
+    let end = tokens.iter().rposition(|t| comment(t).is_none()).map_or(0, |i| i + 1);
+    tokens.split_off(end).iter().filter_map(comment).cloned().collect()
+}
+
+fn currency_comment(token: &CurrencyToken) -> Option<&Comment> {
+    match token {
+        CurrencyToken::Comment(c) => Some(c),
+        _ => None,
+    }
+}
+
+fn item_comment(token: &CategoryItemToken) -> Option<&Comment> {
+    match token {
+        CategoryItemToken::Comment(c) => Some(c),
+        _ => None,
+    }
+}
+
+fn category_comment(token: &TraderCategoryToken) -> Option<&Comment> {
+    match token {
+        TraderCategoryToken::Comment(c) => Some(c),
+        _ => None,
+    }
+}
+
+fn token_attaches(token: &Token) -> bool {
+    matches!(token, Token::Comment(_) | Token::BlankLine)
+}
+
+fn trader_name(token: &Token) -> Option<&str> {
+    match token {
+        Token::Trader(t) => Some(&t.name.text),
+        _ => None,
+    }
+}
+
+fn item_attaches(token: &CategoryItemToken) -> bool {
+    item_comment(token).is_some()
+}
+
+fn item_class(token: &CategoryItemToken) -> Option<&str> {
+    match token {
+        CategoryItemToken::CategoryItem(item) => Some(&item.class),
+        _ => None,
+    }
+}
+
+fn category_attaches(token: &TraderCategoryToken) -> bool {
+    category_comment(token).is_some()
+}
+
+fn category_name(token: &TraderCategoryToken) -> Option<&str> {
+    match token {
+        TraderCategoryToken::TraderCategory(c) => Some(&c.name.text),
+        _ => None,
+    }
+}
+
+// Sorts the named tokens by name, tokens that are neither named nor attached (currencies, the
+// OpenFile and FileEnd tags) are kept in place and only the runs between them are sorted.
+fn sort_attached<T>(tokens: &mut Vec<T>, attaches: fn(&T) -> bool, name: fn(&T) -> Option<&str>) {
+    let mut sorted = Vec::with_capacity(tokens.len());
+    let mut run: Vec<Vec<T>> = Vec::new();
+    let mut pending = Vec::new();
+
+    let flush = |run: &mut Vec<Vec<T>>, sorted: &mut Vec<T>| {
+        // Groups always end with their named token, the sort is stable so equal names keep their order
+        run.sort_by(|a, b| name(a.last().unwrap()).cmp(&name(b.last().unwrap())));
+        sorted.extend(run.drain(..).flatten());
+    };
+
+    for t in tokens.drain(..) {
+        if name(&t).is_some() {
+            pending.push(t);
+            run.push(mem::take(&mut pending));
+        } else if attaches(&t) {
+            pending.push(t);
+        } else {
+            flush(&mut run, &mut sorted);
+            sorted.append(&mut pending);
+            sorted.push(t);
+        }
+    }
+    flush(&mut run, &mut sorted);
+    sorted.append(&mut pending);
+
+    *tokens = sorted;
+}
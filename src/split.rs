@@ -0,0 +1,65 @@
+use crate::token::*;
+
+// Every file gets the tokens before the first trader (OpenFile, currencies and their comments),
+// one trader with the comments and blank lines before it, and the FileEnd tag when there is one.
+// Anything after FileEnd is dropped.
+pub fn split(tokens: Vec<Token>) -> Vec<(String, Vec<Token>)> {
+    let mut header = Vec::new();
+    let mut traders: Vec<(String, Vec<Token>)> = Vec::new();
+    let mut pending = Vec::new();
+    let mut file_end = None;
+
+    for t in tokens.into_iter() {
+        match t {
+            Token::Trader(trader) => {
+                let stem = file_stem(&trader.name.text);
+                // File names are compared ignoring case as Windows does
+                let taken = |n: &str| traders.iter().any(|(t, _)| t.eq_ignore_ascii_case(n));
+                let mut name = format!("{}.txt", stem);
+                let mut i = 2;
+                while taken(&name) {
+                    name = format!("{}_{}.txt", stem, i);
+                    i += 1;
+                }
+                pending.push(Token::Trader(trader));
+                traders.push((name, std::mem::take(&mut pending)));
+            },
+            Token::FileEnd(fe) => {
+                file_end = Some(Token::FileEnd(fe));
+                break;
+            },
+            // Comments right before the first trader stay with it, the others belong to the header
+            t if traders.is_empty() && !matches!(t, Token::Comment(_) | Token::BlankLine) => {
+                header.append(&mut pending);
+                header.push(t);
+            },
+            t => pending.push(t),
+        }
+    }
+
+    traders
+        .into_iter()
+        .map(|(name, trader)| {
+            let mut file = header.clone();
+            file.extend(trader);
+            file.extend(file_end.clone());
+            (name, file)
+        })
+        .collect()
+}
+
+// Keeps letters, digits, dashes and dots, anything else that could be a separator or invalid
+// on Windows becomes an underscore
+fn file_stem(trader: &str) -> String {
+    let stem: String = trader
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    let stem = stem.trim_matches('.');
+    if stem.is_empty() {
+        String::from("Trader")
+    } else {
+        stem.to_string()
+    }
+}
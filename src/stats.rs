@@ -0,0 +1,136 @@
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Location;
+use crate::items::items;
+use crate::token::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Stats {
+    pub traders: Vec<TraderStats>,
+    pub categories: usize,
+    pub items: usize,
+    pub currencies: usize,
+    pub comments: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TraderStats {
+    pub name: String,
+    pub categories: usize,
+    pub items: usize,
+}
+
+// A class listed more than once across the file, in any trader or category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Duplicate {
+    pub class: String,
+    pub listings: Vec<Listing>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Listing {
+    pub trader: String,
+    pub category: String,
+    pub location: Location,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<12}{:>8}", "Traders:", self.traders.len())?;
+        writeln!(f, "{:<12}{:>8}", "Categories:", self.categories)?;
+        writeln!(f, "{:<12}{:>8}", "Items:", self.items)?;
+        writeln!(f, "{:<12}{:>8}", "Currencies:", self.currencies)?;
+        writeln!(f, "{:<12}{:>8}", "Comments:", self.comments)?;
+
+        // Names are padded by how wide they show, format width counts chars
+        let width = self.traders.iter().map(|t| t.name.width()).max().unwrap_or_default();
+        for t in self.traders.iter() {
+            let padding = width - t.name.width();
+            writeln!(f, "    {}{:padding$}{:>8} categories{:>8} items", t.name, "", t.categories, t.items, padding = padding)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn stats(tokens: &[Token]) -> Stats {
+    let mut stats = Stats::default();
+    for t in tokens.iter() {
+        match t {
+            Token::Comment(_) => stats.comments += 1,
+            Token::CurrencyName(c) => {
+                stats.comments += c.name.comments().count();
+                for currency in c.currencies.iter() {
+                    match currency {
+                        CurrencyToken::Comment(_) => stats.comments += 1,
+                        CurrencyToken::Currency(line) => {
+                            stats.currencies += 1;
+                            stats.comments += line.comment.iter().count();
+                        },
+                    }
+                }
+            },
+            Token::Trader(trader) => {
+                let trader_stats = trader_stats(trader, &mut stats.comments);
+                stats.categories += trader_stats.categories;
+                stats.items += trader_stats.items;
+                stats.traders.push(trader_stats);
+            },
+            Token::OpenFile(OpenFile(line)) | Token::FileEnd(FileEnd(line)) => {
+                stats.comments += line.comments().count();
+            },
+            Token::Trailing(_) | Token::BlankLine => (),
+        }
+    }
+    stats
+}
+
+fn trader_stats(trader: &Trader, comments: &mut usize) -> TraderStats {
+    let mut stats = TraderStats { name: trader.name.text.clone(), categories: 0, items: 0 };
+    *comments += trader.name.comments().count();
+    for c in trader.categories.iter() {
+        match c {
+            TraderCategoryToken::Comment(_) => *comments += 1,
+            TraderCategoryToken::TraderCategory(category) => {
+                stats.categories += 1;
+                *comments += category.name.comments().count();
+                for i in category.items.iter() {
+                    match i {
+                        CategoryItemToken::Comment(_) => *comments += 1,
+                        CategoryItemToken::CategoryItem(item) => {
+                            stats.items += 1;
+                            *comments += item.comment.iter().count();
+                        },
+                    }
+                }
+            },
+        }
+    }
+    stats
+}
+
+// Classes listed more than once, the most listed first and ties in the order they first appear
+pub fn duplicates(tokens: &[Token]) -> Vec<Duplicate> {
+    let mut duplicates: Vec<Duplicate> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for (trader, category, item) in items(tokens) {
+        let listing = Listing {
+            trader: trader.name.text.clone(),
+            category: category.name.text.clone(),
+            location: item.location,
+        };
+        match index.get(item.class.as_str()) {
+            Some(&i) => duplicates[i].listings.push(listing),
+            None => {
+                index.insert(&item.class, duplicates.len());
+                duplicates.push(Duplicate { class: item.class.clone(), listings: vec![listing] });
+            },
+        }
+    }
+    duplicates.retain(|d| d.listings.len() > 1);
+    duplicates.sort_by_key(|d| Reverse(d.listings.len()));
+    duplicates
+}
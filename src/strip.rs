@@ -0,0 +1,45 @@
+use crate::token::*;
+
+// Removes every comment, the ones on their own line and the ones after a value. A raw header is
+// dropped with its comment since it's written as it was, comment included.
+pub fn strip_comments(tokens: &mut Vec<Token>) {
+    tokens.retain(|t| !matches!(t, Token::Comment(_)));
+    for t in tokens.iter_mut() {
+        match t {
+            Token::CurrencyName(c) => {
+                strip_line(&mut c.name);
+                c.currencies.retain(|c| !matches!(c, CurrencyToken::Comment(_)));
+                for currency in c.currencies.iter_mut() {
+                    if let CurrencyToken::Currency(line) = currency {
+                        line.comment = None;
+                    }
+                }
+            },
+            Token::Trader(trader) => {
+                strip_line(&mut trader.name);
+                trader.categories.retain(|c| !matches!(c, TraderCategoryToken::Comment(_)));
+                for c in trader.categories.iter_mut() {
+                    if let TraderCategoryToken::TraderCategory(category) = c {
+                        strip_line(&mut category.name);
+                        category.items.retain(|i| !matches!(i, CategoryItemToken::Comment(_)));
+                        for i in category.items.iter_mut() {
+                            if let CategoryItemToken::CategoryItem(item) = i {
+                                item.comment = None;
+                            }
+                        }
+                    }
+                }
+            },
+            Token::OpenFile(OpenFile(line)) | Token::FileEnd(FileEnd(line)) => strip_line(line),
+            Token::Comment(_) | Token::Trailing(_) | Token::BlankLine => (),
+        }
+    }
+}
+
+fn strip_line(line: &mut Line) {
+    if line.comments().next().is_some() {
+        line.block_comments.clear();
+        line.comment = None;
+        line.raw = None;
+    }
+}
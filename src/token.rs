@@ -0,0 +1,697 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::error::{Location, ParseError};
+
+pub const PADDING: usize =  60;
+// Tab delimited values are padded with tabs, assuming a tab stop every TAB_WIDTH characters
+const TAB_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn detect(contents: &str) -> Self {
+        let crlf = contents.matches("\r\n").count();
+        let lf = contents.matches('\n').count() - crlf;
+        if crlf > lf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Indent {
+    // The layout the formatter always had, currencies are indented 8 spaces, categories and the
+    // comments between them 4 and items 16
+    #[default]
+    Legacy,
+    // Every nesting level is indented by this many spaces
+    Spaces(usize),
+    // Every nesting level is indented by this many tabs
+    Tabs(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Nesting {
+    Currency,
+    Category,
+    Item,
+}
+
+impl Indent {
+    fn of(&self, nesting: Nesting) -> String {
+        let depth = match nesting {
+            Nesting::Currency | Nesting::Category => 1,
+            Nesting::Item => 2,
+        };
+        match (self, nesting) {
+            (Indent::Legacy, Nesting::Currency) => " ".repeat(8),
+            (Indent::Legacy, Nesting::Category) => " ".repeat(4),
+            (Indent::Legacy, Nesting::Item) => " ".repeat(16),
+            (Indent::Spaces(n), _) => " ".repeat(n * depth),
+            (Indent::Tabs(n), _) => "\t".repeat(n * depth),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommentColumn {
+    // Comments follow the padded values
+    #[default]
+    Unaligned,
+    // Comments in a category line up one space past its widest item
+    Widest,
+    // Comments start this many characters into the line, or one space past a longer item
+    Fixed(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemField {
+    Class,
+    Amount,
+    BuyValue,
+    SellValue,
+}
+
+impl ItemField {
+    const ALL: [ItemField; 4] = [ItemField::Class, ItemField::Amount, ItemField::BuyValue, ItemField::SellValue];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemField::Class => "class",
+            ItemField::Amount => "amount",
+            ItemField::BuyValue => "buy_value",
+            ItemField::SellValue => "sell_value",
+        }
+    }
+}
+
+// The order the four values of a category item are written in, the trader itself reads
+// class, amount, buy value, sell value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnOrder(pub [ItemField; 4]);
+
+impl Default for ColumnOrder {
+    fn default() -> Self {
+        ColumnOrder(ItemField::ALL)
+    }
+}
+
+impl FromStr for ColumnOrder {
+    type Err = String;
+
+    // A comma separated list of the field names, each one exactly once
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+        for name in s.split(',').map(str::trim) {
+            let field = ItemField::ALL.into_iter().find(|f| f.name() == name).ok_or_else(|| {
+                format!("unknown field \"{}\", expected class, amount, buy_value or sell_value", name)
+            })?;
+            if fields.contains(&field) {
+                return Err(format!("field \"{}\" is listed more than once", name));
+            }
+            fields.push(field);
+        }
+        fields.try_into().map(ColumnOrder).map_err(|_| {
+            "expected each of class, amount, buy_value and sell_value once".to_string()
+        })
+    }
+}
+
+impl fmt::Display for ColumnOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(|field| field.name()).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    pub width: usize,
+    pub line_ending: LineEnding,
+    // When false every block is followed by one blank line and blank lines in the input are dropped
+    pub preserve_blank_lines: bool,
+    pub indent: Indent,
+    // Where trailing comments of category items start
+    pub comment_column: CommentColumn,
+    // When true values are joined by ", " instead of being padded to width
+    pub compact: bool,
+    pub column_order: ColumnOrder,
+    // Separates values, a tab also pads them
+    pub delimiter: char,
+    // Comments on their own line are written in this style, block comments are kept as they are
+    pub comment_style: Option<CommentStyle>,
+    // Category headers end their comment with the number of items, replacing the count written by
+    // an earlier run
+    pub annotate_counts: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            width: PADDING,
+            line_ending: LineEnding::default(),
+            preserve_blank_lines: false,
+            indent: Indent::default(),
+            comment_column: CommentColumn::default(),
+            compact: false,
+            column_order: ColumnOrder::default(),
+            delimiter: ',',
+            comment_style: None,
+            annotate_counts: false,
+        }
+    }
+}
+
+pub trait Format {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result;
+}
+
+pub struct Formatted<'a, T: Format>(pub &'a T, pub &'a FormatOptions);
+
+impl<T: Format> fmt::Display for Formatted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.format(f, self.1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub text: String,
+    #[serde(default)]
+    pub style: CommentStyle,
+    // The text is kept exactly as written after the comment marker, leading spaces included
+    #[serde(default)]
+    pub raw: bool,
+    #[serde(skip)]
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CommentStyle {
+    // A // comment running until the end of the line
+    #[default]
+    Line,
+    // A /* */ comment, its text is kept as written and can span several lines
+    Block,
+    // A ; comment on its own line, as used by some trader mods
+    Semicolon,
+    // A # comment on its own line, as used by some trader mods
+    Hash,
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let space = if self.raw || self.text.is_empty() { "" } else { " " };
+        match self.style {
+            CommentStyle::Line => write!(f, "//{}{}", space, self.text),
+            CommentStyle::Block => write!(f, "/*{}*/", self.text),
+            CommentStyle::Semicolon => write!(f, ";{}{}", space, self.text),
+            CommentStyle::Hash => write!(f, "#{}{}", space, self.text),
+        }
+    }
+}
+
+// Formats a comment on its own line. Comments after values are written with Display, they're
+// always // or block comments since ; and # only start a comment at the start of a line
+impl Format for Comment {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match opts.comment_style {
+            Some(style) if self.style != CommentStyle::Block && style != CommentStyle::Block => {
+                write!(f, "{}", Comment { style, ..self.clone() })
+            },
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Line {
+    pub text: String,
+    // Block comments closed before the end of the line with more written after them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_comments: Vec<Comment>,
+    pub comment: Option<Comment>,
+    // The rest of a header line exactly as written after its tag, written instead of the text and
+    // comment when there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+impl Line {
+    // The block comments and the comment ending the line, in the order they're written
+    pub fn comments(&self) -> impl Iterator<Item = &Comment> {
+        self.block_comments.iter().chain(self.comment.iter())
+    }
+}
+
+impl Format for Line {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        write!(f, "{}", self.text)?;
+        for (i, c) in self.comments().enumerate() {
+            if i == 0 && self.text.is_empty() {
+                write!(f, "{}", c)?;
+            } else {
+                write!(f, " {}", c)?;
+            }
+        }
+        write!(f, "{}", opts.line_ending)
+    }
+}
+
+// The space between a tag and its line, left out when there's nothing after the tag
+fn separator(line: &Line) -> &'static str {
+    if line.text.is_empty() && line.comments().next().is_none() {
+        ""
+    } else {
+        " "
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CSVLine {
+    pub values: Vec<String>,
+    pub comment: Option<Comment>,
+    #[serde(skip)]
+    pub location: Location,
+}
+
+impl Format for CSVLine {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        if opts.compact {
+            let values: Vec<Cow<str>> = self.values.iter().map(|v| quote(v, opts.delimiter)).collect();
+            write!(f, "{}", values.join(&separator_of(opts.delimiter)))?;
+            if let Some(c) = self.comment.as_ref() {
+                write!(f, " {}", c)?;
+            }
+            return write!(f, "{}", opts.line_ending);
+        }
+
+        // CSV lines are only written after <Currency>, which is where tab stops are counted from
+        let start = column_of(&format!("{}<Currency> ", opts.indent.of(Nesting::Currency)));
+        let values: Vec<Cow<str>> = self.values.iter().map(|v| quote(v, opts.delimiter)).collect();
+        // The last value is only padded to line up the comment after it
+        write!(f, "{}", pad(&values, [opts.width, opts.width], self.comment.is_some(), opts.delimiter, start))?;
+
+        if let Some(c) = self.comment.as_ref() {
+            write!(f, " {}", c)?;
+        }
+
+        write!(f, "{}", opts.line_ending)
+    }
+}
+
+
+// What goes between values when they aren't padded
+fn separator_of(delimiter: char) -> String {
+    match delimiter {
+        '\t' => "\t".to_string(),
+        d => format!("{} ", d),
+    }
+}
+
+// Each value is followed by its delimiter and padded to the width of its column, widths holds the
+// one of the first column and then the one of the rest. With tabs the padding is the delimiter
+// itself, up to the first tab stop past the width, the parser skips the empty values in between.
+fn pad(values: &[Cow<str>], widths: [usize; 2], pad_last: bool, delimiter: char, start: usize) -> String {
+    let mut out = String::new();
+    let mut column = start;
+    for (i, v) in values.iter().enumerate() {
+        let last = i == values.len() - 1;
+        let width = widths[(i > 0) as usize];
+        if last && !pad_last {
+            out.push_str(v);
+        } else if delimiter == '\t' {
+            let end = column + v.width();
+            let next = (column + width).max(end + 1).div_ceil(TAB_WIDTH) * TAB_WIDTH;
+            out.push_str(v);
+            out.push_str(&"\t".repeat(next / TAB_WIDTH - end / TAB_WIDTH));
+            column = next;
+        } else {
+            let value = if last { v.to_string() } else { format!("{}{}", v, delimiter) };
+            // A value as wide as its column or wider still gets a space before the next one
+            let padding = width.saturating_sub(value.width()).max(!last as usize);
+            out.push_str(&format!("{}{:padding$}", value, "", padding = padding));
+        }
+    }
+    out
+}
+
+// The column text ends at, with tabs moving to the next tab stop and wide characters taking two
+pub(crate) fn column_of(text: &str) -> usize {
+    text.chars().fold(0, |column, c| match c {
+        '\t' => (column / TAB_WIDTH + 1) * TAB_WIDTH,
+        c => column + c.width().unwrap_or_default(),
+    })
+}
+
+// Values that would otherwise be split or cut short by the parser are wrapped in double quotes
+fn quote(value: &str, delimiter: char) -> Cow<'_, str> {
+    let needs_quotes = value.contains([delimiter, '"'])
+        || value.contains("//")
+        || value.contains("/*")
+        || value.starts_with(['<', ';', '#'])
+        || value != value.trim();
+    if needs_quotes {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CurrencyToken {
+    Comment(Comment),
+    Currency(CSVLine)
+}
+
+impl Format for CurrencyToken {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match self {
+            CurrencyToken::Comment(c) => {
+                write!(f, "{}{}{}", opts.indent.of(Nesting::Currency), Formatted(c, opts), opts.line_ending)
+            },
+            CurrencyToken::Currency(c) => {
+                write!(f, "{}<Currency> {}", opts.indent.of(Nesting::Currency), Formatted(c, opts))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyName {
+    pub name: Line,
+    pub currencies: Vec<CurrencyToken>
+}
+
+impl Format for CurrencyName {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        write!(f, "<CurrencyName>{}{}", separator(&self.name), Formatted(&self.name, opts))?;
+        for c in self.currencies.iter() {
+            c.format(f, opts)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryItem {
+    pub class: String,
+    pub amount: String,
+    pub buy_value: String,
+    pub sell_value: String,
+    // Stock limit some trader mods read from a fifth column, vanilla configs don't have it
+    #[serde(default)]
+    pub stock: Option<String>,
+    pub comment: Option<Comment>,
+    #[serde(skip)]
+    pub location: Location,
+}
+
+impl TryFrom<&CSVLine> for CategoryItem {
+    type Error = ParseError;
+
+    fn try_from(value: &CSVLine) -> Result<Self, Self::Error> {
+        if !(4..=5).contains(&value.values.len()) {
+            return Err(ParseError::MissingCsvValues {
+                found: value.values.len(),
+                line: value.values.join(", "),
+                location: value.location,
+            })
+        }
+
+        Ok(CategoryItem {
+            class: value.values.first().unwrap().clone(),
+            amount: value.values.get(1).unwrap().clone(),
+            buy_value: value.values.get(2).unwrap().clone(),
+            sell_value: value.values.get(3).unwrap().clone(),
+            stock: value.values.get(4).cloned(),
+            comment: value.comment.clone(),
+            location: value.location,
+        })
+    }
+}
+
+impl CategoryItem {
+    pub fn field(&self, field: ItemField) -> &str {
+        match field {
+            ItemField::Class => &self.class,
+            ItemField::Amount => &self.amount,
+            ItemField::BuyValue => &self.buy_value,
+            ItemField::SellValue => &self.sell_value,
+        }
+    }
+
+    fn field_mut(&mut self, field: ItemField) -> &mut String {
+        match field {
+            ItemField::Class => &mut self.class,
+            ItemField::Amount => &mut self.amount,
+            ItemField::BuyValue => &mut self.buy_value,
+            ItemField::SellValue => &mut self.sell_value,
+        }
+    }
+
+    // Items are always read as class, amount, buy value, sell value, for a file written in another
+    // order the values are moved to the fields they were written for
+    pub(crate) fn reorder(&mut self, order: ColumnOrder) {
+        let read = [self.class.clone(), self.amount.clone(), self.buy_value.clone(), self.sell_value.clone()];
+        for (field, value) in order.0.into_iter().zip(read) {
+            *self.field_mut(field) = value;
+        }
+    }
+
+    fn values(&self, opts: &FormatOptions) -> String {
+        let values: Vec<Cow<str>> = opts.column_order.0.iter()
+            .map(|field| self.field(*field))
+            .chain(self.stock.as_deref())
+            .map(|v| quote(v, opts.delimiter))
+            .collect();
+        if opts.compact {
+            return values.join(&separator_of(opts.delimiter));
+        }
+
+        let start = column_of(opts.indent.of(Nesting::Item).as_ref());
+        pad(&values, [opts.width, 10], true, opts.delimiter, start)
+    }
+}
+
+impl Format for CategoryItem {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match (opts.compact, self.comment.as_ref()) {
+            (_, None) => write!(f, "{}", self.values(opts).trim_end()),
+            (true, Some(c)) => write!(f, "{} {}", self.values(opts), c),
+            (false, Some(c)) => {
+                let values = self.values(opts);
+                let space = if values.ends_with(char::is_whitespace) { "" } else { " " };
+                write!(f, "{}{}{}", values, space, c)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CategoryItemToken {
+    CategoryItem(CategoryItem),
+    Comment(Comment)
+}
+
+impl Format for CategoryItemToken {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match self {
+            CategoryItemToken::Comment(c) => {
+                write!(f, "{}{}{}", opts.indent.of(Nesting::Item), Formatted(c, opts), opts.line_ending)
+            },
+            CategoryItemToken::CategoryItem(c) => match (opts.comment_column, c.comment.as_ref()) {
+                (CommentColumn::Fixed(column), Some(comment)) => {
+                    let line = format!("{}{}", opts.indent.of(Nesting::Item), c.values(opts).trim_end());
+                    let padding = column.saturating_sub(column_of(&line)).max(1);
+                    write!(f, "{}{:padding$}{}{}", line, "", comment, opts.line_ending, padding = padding)
+                },
+                _ => write!(f, "{}{}{}", opts.indent.of(Nesting::Item), Formatted(c, opts), opts.line_ending),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraderCategory {
+    pub name: Line,
+    pub items: Vec<CategoryItemToken>,
+}
+
+impl Format for TraderCategory {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        let name = if opts.annotate_counts { Cow::Owned(self.annotated_name()) } else { Cow::Borrowed(&self.name) };
+        let indent = opts.indent.of(Nesting::Category);
+        write!(f, "{}<Category>{}{}", indent, separator(&name), Formatted(name.as_ref(), opts))?;
+        let opts = self.item_options(opts);
+        for c in self.items.iter() {
+            c.format(f, &opts)?;
+        }
+        Ok(())
+    }
+}
+
+impl TraderCategory {
+    // The name with a comment like [42 items] at the end, after the text of the comment already there
+    fn annotated_name(&self) -> Line {
+        let count = self.items.iter().filter(|i| matches!(i, CategoryItemToken::CategoryItem(_))).count();
+        let annotation = format!("[{} item{}]", count, if count == 1 { "" } else { "s" });
+        let annotated = |text: &str| match text {
+            "" => annotation.clone(),
+            text => format!("{} {}", text, annotation),
+        };
+        let comment = match self.name.comment.as_ref() {
+            Some(c) if c.style == CommentStyle::Block => {
+                Comment { text: format!(" {} ", annotated(without_count(&c.text).trim())), ..c.clone() }
+            },
+            Some(c) => Comment { text: annotated(without_count(&c.text)), ..c.clone() },
+            None => Comment { text: annotated(""), style: CommentStyle::Line, raw: false, location: Location::default() },
+        };
+        Line { comment: Some(comment), ..self.name.clone() }
+    }
+
+    // Items only see a fixed column, the widest one is worked out for the whole category here
+    pub(crate) fn item_options<'a>(&self, opts: &'a FormatOptions) -> Cow<'a, FormatOptions> {
+        if opts.comment_column != CommentColumn::Widest {
+            return Cow::Borrowed(opts);
+        }
+        let indent = opts.indent.of(Nesting::Item).width();
+        let widest = self.items.iter()
+            .filter_map(|i| match i {
+                CategoryItemToken::CategoryItem(item) => Some(indent + item.values(opts).trim_end().width()),
+                CategoryItemToken::Comment(_) => None,
+            })
+            .max()
+            .unwrap_or_default();
+        Cow::Owned(FormatOptions { comment_column: CommentColumn::Fixed(widest + 1), ..opts.clone() })
+    }
+}
+
+// The text of a comment without the count --annotate-counts added to its end
+pub(crate) fn without_count(text: &str) -> &str {
+    let trimmed = text.trim_end();
+    let Some(start) = trimmed.rfind('[') else { return text };
+    let count = trimmed[start + 1..].strip_suffix(" items]").or_else(|| trimmed[start + 1..].strip_suffix(" item]"));
+    match count {
+        Some(count) if !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()) => trimmed[..start].trim_end(),
+        _ => text,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TraderCategoryToken {
+    TraderCategory(TraderCategory),
+    Comment(Comment)
+}
+
+impl Format for TraderCategoryToken {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match self {
+            TraderCategoryToken::Comment(c) => {
+                write!(f, "{}{}{}", opts.indent.of(Nesting::Category), Formatted(c, opts), opts.line_ending)
+            },
+            TraderCategoryToken::TraderCategory(c) => c.format(f, opts)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trader {
+    pub name: Line,
+    pub categories: Vec<TraderCategoryToken>
+}
+
+impl Format for Trader {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        write!(f, "<Trader>{}{}", separator(&self.name), Formatted(&self.name, opts))?;
+        for c in self.categories.iter() {
+            c.format(f, opts)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenFile(pub Line);
+
+impl Format for OpenFile {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match self.0.raw.as_ref() {
+            Some(raw) => write!(f, "<OpenFile>{}{}", raw, opts.line_ending),
+            None => write!(f, "<OpenFile>{}{}", separator(&self.0), Formatted(&self.0, opts)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEnd(pub Line);
+
+impl Format for FileEnd {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        write!(f, "<FileEnd>{}{}", separator(&self.0), Formatted(&self.0, opts))
+    }
+}
+
+// Whatever follows <FileEnd>. The trader stops reading there, so it's kept exactly as written
+// instead of being parsed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trailing {
+    pub text: String,
+    #[serde(skip)]
+    pub location: Location,
+}
+
+impl Format for Trailing {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        write!(f, "{}{}", self.text, opts.line_ending)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Token {
+    Comment(Comment),
+    CurrencyName(CurrencyName),
+    Trader(Trader),
+    OpenFile(OpenFile),
+    FileEnd(FileEnd),
+    Trailing(Trailing),
+    BlankLine,
+}
+
+impl Format for Token {
+    fn format(&self, f: &mut fmt::Formatter<'_>, opts: &FormatOptions) -> fmt::Result {
+        match self {
+            Token::Comment(c) => write!(f, "{}{}", Formatted(c, opts), opts.line_ending),
+            Token::CurrencyName(c) => c.format(f, opts),
+            Token::Trader(t) => t.format(f, opts),
+            Token::OpenFile(o) => o.format(f, opts),
+            Token::FileEnd(fe) => fe.format(f, opts),
+            Token::Trailing(t) => t.format(f, opts),
+            Token::BlankLine => write!(f, "{}", opts.line_ending),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::items::items;
+use crate::normalize::without_thousands_separators;
+use crate::token::*;
+
+// Quantity markers understood by the trader besides plain numbers, * is the max quantity and
+// the others are vehicles, vehicles without keys, magazines, weapons, steaks and keys.
+const AMOUNT_MARKERS: [&str; 7] = ["*", "V", "VNK", "M", "W", "S", "K"];
+pub(crate) const NOT_TRADEABLE: &str = "-1";
+const MAX_AMOUNT: u64 = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub trader: Option<String>,
+    pub category: Option<String>,
+    pub class: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let context: Vec<&str> = [&self.trader, &self.category, &self.class]
+            .into_iter()
+            .filter_map(|c| c.as_deref())
+            .collect();
+        if !context.is_empty() {
+            write!(f, "{}: ", context.join(" > "))?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidateOptions {
+    // Amounts above this are reported, an extra digit typed by accident is a likely cause and the
+    // game's parser overflows on very large numbers
+    pub max_amount: u64,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        ValidateOptions {
+            max_amount: MAX_AMOUNT,
+        }
+    }
+}
+
+pub fn validate(tokens: &[Token]) -> Vec<Violation> {
+    validate_with(tokens, &ValidateOptions::default())
+}
+
+pub fn validate_with(tokens: &[Token], opts: &ValidateOptions) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for t in tokens.iter() {
+        match t {
+            Token::Trader(trader) => validate_trader(trader, opts, &mut violations),
+            Token::OpenFile(open_file) => violations.extend(validate_open_file(open_file)),
+            Token::CurrencyName(currency_name) => validate_currency_name(currency_name, &mut violations),
+            _ => (),
+        }
+    }
+    violations
+}
+
+// The trader reads the file named after <OpenFile> from the same folder, configs are .txt files
+fn validate_open_file(open_file: &OpenFile) -> Option<Violation> {
+    let name = open_file.0.text.as_str();
+    let message = if name.is_empty() {
+        "<OpenFile> has no file name".to_string()
+    } else if !name.to_lowercase().ends_with(".txt") {
+        format!("<OpenFile> file name \"{}\" is not a .txt file", name)
+    } else {
+        return None;
+    };
+    Some(Violation { trader: None, category: None, class: None, message })
+}
+
+// Each <Currency> is a class and the value of one of it, in the smallest denomination
+fn validate_currency_name(currency_name: &CurrencyName, violations: &mut Vec<Violation>) {
+    let name = currency_name.name.text.as_str();
+    if name.is_empty() {
+        violations.push(Violation {
+            trader: None,
+            category: None,
+            class: None,
+            message: "<CurrencyName> has no name".to_string(),
+        });
+    }
+
+    for c in currency_name.currencies.iter() {
+        let CurrencyToken::Currency(currency) = c else { continue };
+        let message = match currency.values.as_slice() {
+            [_, value] if value.parse::<u64>().is_ok_and(|v| v > 0) => continue,
+            [_, value] => format!("value \"{}\" of <CurrencyName> {} is not a positive number", value, name),
+            values => {
+                format!("<Currency> of <CurrencyName> {} has {} values instead of 2 (class, value)", name, values.len())
+            },
+        };
+        violations.push(Violation {
+            trader: None,
+            category: None,
+            class: currency.values.first().cloned(),
+            message,
+        });
+    }
+}
+
+fn validate_trader(trader: &Trader, opts: &ValidateOptions, violations: &mut Vec<Violation>) {
+    // Categories each class is listed in, in the order the classes first appear
+    let mut categories: Vec<(&str, Vec<&str>)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for c in trader.categories.iter() {
+        if let TraderCategoryToken::TraderCategory(category) = c {
+            let mut seen: HashMap<&str, &CategoryItem> = HashMap::new();
+            for i in category.items.iter() {
+                if let CategoryItemToken::CategoryItem(item) = i {
+                    let mut messages = validate_item(item, opts);
+                    if let Some(first) = seen.get(item.class.as_str()) {
+                        messages.push(format!(
+                            "class is listed more than once in the category, first at {} and again at {}",
+                            first.location, item.location
+                        ));
+                    } else {
+                        seen.insert(&item.class, item);
+                        match index.get(item.class.as_str()) {
+                            Some(&i) => categories[i].1.push(&category.name.text),
+                            None => {
+                                index.insert(&item.class, categories.len());
+                                categories.push((&item.class, vec![&category.name.text]));
+                            },
+                        }
+                    }
+
+                    let violation = |message: String| Violation {
+                        trader: Some(trader.name.text.clone()),
+                        category: Some(category.name.text.clone()),
+                        class: Some(item.class.clone()),
+                        message,
+                    };
+                    violations.extend(messages.into_iter().map(violation));
+                }
+            }
+        }
+    }
+
+    // Players see the same class priced differently depending on the category they browse
+    for (class, names) in categories.into_iter().filter(|(_, names)| names.len() > 1) {
+        violations.push(Violation {
+            trader: Some(trader.name.text.clone()),
+            category: None,
+            class: Some(class.to_string()),
+            message: format!("class is listed in more than one category: {}", names.join(", ")),
+        });
+    }
+}
+
+// Advisory, some config editors don't render lines past a certain width well. Items are measured
+// as they'd be formatted with the given options, tabs moving to the next tab stop.
+pub fn long_lines(tokens: &[Token], opts: &FormatOptions, max_width: usize) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (trader, category, items) in categories(tokens) {
+        let opts = category.item_options(opts);
+        for i in items {
+            let CategoryItemToken::CategoryItem(item) = i else { continue };
+            let formatted = Formatted(i, &opts).to_string();
+            let width = formatted.lines().map(column_of).max().unwrap_or_default();
+            if width > max_width {
+                violations.push(Violation {
+                    trader: Some(trader.name.text.clone()),
+                    category: Some(category.name.text.clone()),
+                    class: Some(item.class.clone()),
+                    message: format!("line is {} characters wide, more than {}", width, max_width),
+                });
+            }
+        }
+    }
+    violations
+}
+
+// Opt-in, an item that sells for more than it's bought for can be bought and sold back over and
+// over for money. Items either side doesn't trade (-1) or that aren't plain numbers are skipped.
+pub fn profitable_resales(tokens: &[Token]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (trader, category, item) in items(tokens) {
+        let (Ok(buy), Ok(sell)) = (item.buy_value.parse::<i64>(), item.sell_value.parse::<i64>()) else { continue };
+        if buy != -1 && sell != -1 && sell > buy {
+            let message = format!(
+                "sells for {}, more than it's bought for ({}), so it can be resold for a profit",
+                sell, buy
+            );
+            violations.push(Violation {
+                trader: Some(trader.name.text.clone()),
+                category: Some(category.name.text.clone()),
+                class: Some(item.class.clone()),
+                message,
+            });
+        }
+    }
+    violations
+}
+
+fn categories(tokens: &[Token]) -> impl Iterator<Item = (&Trader, &TraderCategory, &[CategoryItemToken])> {
+    tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Trader(trader) => Some(trader),
+            _ => None,
+        })
+        .flat_map(|trader| {
+            trader.categories.iter().filter_map(move |c| match c {
+                TraderCategoryToken::TraderCategory(category) => Some((trader, category, category.items.as_slice())),
+                TraderCategoryToken::Comment(_) => None,
+            })
+        })
+}
+
+fn validate_item(item: &CategoryItem, opts: &ValidateOptions) -> Vec<String> {
+    let mut messages = Vec::new();
+    let amount = item.amount.as_str();
+    let is_number = !amount.is_empty() && amount.bytes().all(|b| b.is_ascii_digit());
+    if is_number && amount.parse::<u64>().map_or(true, |a| a > opts.max_amount) {
+        messages.push(format!("amount {} is more than the maximum of {}", amount, opts.max_amount));
+    } else if !is_number && amount != NOT_TRADEABLE && !AMOUNT_MARKERS.contains(&amount) {
+        messages.push(format!("amount \"{}\" is not a number or a quantity marker", item.amount));
+    }
+    for (name, value) in [("buy value", &item.buy_value), ("sell value", &item.sell_value)] {
+        if is_price(value) || is_percentage(value) {
+            continue;
+        }
+        match without_thousands_separators(value) {
+            Some(digits) => messages.push(format!(
+                "{} \"{}\" has thousands separators DayZ doesn't read, write it as {}",
+                name, value, digits
+            )),
+            None => messages.push(format!("{} \"{}\" is not a number, a percentage or -1", name, value)),
+        }
+    }
+    messages
+}
+
+fn is_price(value: &str) -> bool {
+    value == NOT_TRADEABLE || value.parse::<u64>().is_ok()
+}
+
+// Some trader variants take values like 25%, a percentage of the base price
+fn is_percentage(value: &str) -> bool {
+    value.strip_suffix('%').is_some_and(|v| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()))
+}
@@ -0,0 +1,93 @@
+use trader_config_formatter::{format_with, parse, CommentColumn, FormatOptions, Indent};
+use unicode_width::UnicodeWidthStr;
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500 // gun
+        M4A1_Very_Long_Class_Name_For_Testing, *, 2000, 1000 // rifle
+        SVD, *, 5000, 2500
+        Mosin, *, 300, 150 // cheap
+<FileEnd>
+";
+
+fn comment_columns(formatted: &str) -> Vec<usize> {
+    formatted.lines().filter_map(|l| l.find("//")).collect()
+}
+
+#[test]
+fn comments_line_up_past_the_widest_item() {
+    let opts = FormatOptions { width: 10, comment_column: CommentColumn::Widest, ..FormatOptions::default() };
+    let formatted = format_with(&parse(CONFIG).unwrap(), &opts);
+    let columns = comment_columns(&formatted);
+    assert_eq!(columns.len(), 3);
+    assert!(columns.iter().all(|c| *c == columns[0]));
+
+    let widest = formatted.lines().find(|l| l.contains("M4A1")).unwrap();
+    assert_eq!(&widest[columns[0] - 1..columns[0]], " ");
+    assert_ne!(&widest[columns[0] - 2..columns[0] - 1], " ");
+}
+
+#[test]
+fn comments_start_at_a_fixed_column_or_one_space_past_longer_items() {
+    let opts = FormatOptions { width: 10, comment_column: CommentColumn::Fixed(60), ..FormatOptions::default() };
+    let formatted = format_with(&parse(CONFIG).unwrap(), &opts);
+    let long = formatted.lines().find(|l| l.contains("M4A1")).unwrap();
+    let long_column = long.find("//").unwrap();
+    assert!(long_column > 60);
+    assert_eq!(&long[long_column - 2..long_column], "0 ");
+
+    let columns = comment_columns(&formatted);
+    assert_eq!(columns, [60, long_column, 60]);
+}
+
+#[test]
+fn aligned_comments_survive_a_round_trip() {
+    for comment_column in [CommentColumn::Widest, CommentColumn::Fixed(60)] {
+        let opts = FormatOptions { comment_column, ..FormatOptions::default() };
+        let first = format_with(&parse(CONFIG).unwrap(), &opts);
+        assert_eq!(format_with(&parse(&first).unwrap(), &opts), first);
+    }
+}
+
+#[test]
+fn wide_and_combining_characters_are_padded_by_display_width() {
+    let config = "\
+<Trader> Boris
+    <Category> Blades
+        Katana_\u{5263}\u{9053}, *, 1000, 500 // wide
+        Cafe\u{301}_Knife, *, 200, 100 // combining
+        Kitchen_Knife, *, 50, 25 // plain
+<FileEnd>
+";
+    let opts = FormatOptions { width: 18, comment_column: CommentColumn::Widest, ..FormatOptions::default() };
+    let formatted = format_with(&parse(config).unwrap(), &opts);
+    let items: Vec<&str> = formatted.lines().filter(|l| l.contains("//")).collect();
+    assert_eq!(items.len(), 3);
+
+    // Columns are compared as they show on screen, not as characters or bytes
+    let display_column = |line: &str, text: &str| line[..line.find(text).unwrap()].width();
+    let stock: Vec<usize> = items.iter().map(|l| display_column(l, "*")).collect();
+    assert!(stock.iter().all(|c| *c == stock[0]), "{}", formatted);
+    let comments: Vec<usize> = items.iter().map(|l| display_column(l, "//")).collect();
+    assert!(comments.iter().all(|c| *c == comments[0]), "{}", formatted);
+    assert_eq!(format_with(&parse(&formatted).unwrap(), &opts), formatted);
+}
+
+#[test]
+fn fixed_comment_column_counts_tab_indents_to_the_next_tab_stop() {
+    let opts = FormatOptions {
+        width: 10,
+        indent: Indent::Tabs(1),
+        comment_column: CommentColumn::Fixed(60),
+        ..FormatOptions::default()
+    };
+    let formatted = format_with(&parse(CONFIG).unwrap(), &opts);
+    let line = formatted.lines().find(|l| l.contains("// gun")).unwrap();
+    assert!(line.starts_with("\t\tAKM"), "{:?}", line);
+
+    // Each of the two tabs reaches the next stop 8 columns on
+    let before = &line[..line.find("//").unwrap()];
+    assert_eq!(16 + before.trim_start_matches('\t').width(), 60, "{:?}", line);
+    assert_eq!(format_with(&parse(&formatted).unwrap(), &opts), formatted);
+}
@@ -0,0 +1,43 @@
+use trader_config_formatter::{format_with, parse, FormatOptions};
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons // WIP
+        AKM, *, 1000, 500
+        // rifles
+        M4A1, *, 2000, 1000
+    <Category> Ammo /* boxes */
+        Ammo_762x39, *, 10, 5
+    <Category> Empty
+<FileEnd>
+";
+
+fn annotated(contents: &str) -> String {
+    let opts = FormatOptions { annotate_counts: true, compact: true, ..FormatOptions::default() };
+    format_with(&parse(contents).unwrap(), &opts)
+}
+
+#[test]
+fn category_lines_end_with_the_number_of_items() {
+    let formatted = annotated(CONFIG);
+    assert!(formatted.contains("<Category> Weapons // WIP [2 items]\n"), "{}", formatted);
+    assert!(formatted.contains("<Category> Ammo /* boxes [1 item] */\n"), "{}", formatted);
+    assert!(formatted.contains("<Category> Empty // [0 items]\n"), "{}", formatted);
+}
+
+#[test]
+fn counts_are_refreshed_instead_of_added_again() {
+    let formatted = annotated(CONFIG);
+    assert_eq!(annotated(&formatted), formatted);
+
+    let grown = formatted.replace("Ammo_762x39, *, 10, 5\n", "Ammo_762x39, *, 10, 5\n        Ammo_545x39, *, 10, 5\n");
+    let refreshed = annotated(&grown);
+    assert!(refreshed.contains("<Category> Ammo /* boxes [2 items] */\n"), "{}", refreshed);
+    assert_eq!(refreshed.matches("items]").count(), 3, "{}", refreshed);
+}
+
+#[test]
+fn brackets_that_are_not_a_count_are_kept() {
+    let formatted = annotated("<Trader> Boris\n    <Category> Weapons // see [wiki]\n        AKM, *, 1000, 500\n");
+    assert!(formatted.contains("<Category> Weapons // see [wiki] [1 item]\n"), "{}", formatted);
+}
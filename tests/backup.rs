@@ -0,0 +1,66 @@
+use std::fs;
+
+mod common;
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+
+#[test]
+fn backup_keeps_the_original() {
+    let dir = common::temp_dir("backup");
+    let file = dir.join("TraderConfig.txt");
+    fs::write(&file, CONFIG).unwrap();
+
+    let run = || {
+        common::formatter()
+            .args(["--in-place", "--backup"])
+            .arg(&file)
+            .assert()
+            .success();
+    };
+
+    run();
+    assert_eq!(fs::read_to_string(dir.join("TraderConfig.txt.bak")).unwrap(), CONFIG);
+    assert_eq!(fs::read_to_string(&file).unwrap(), FORMATTED);
+
+    // The first backup is kept, the second one gets a counter
+    fs::write(&file, CONFIG.replace("AKM", "AK74")).unwrap();
+    run();
+    assert_eq!(fs::read_to_string(dir.join("TraderConfig.txt.bak")).unwrap(), CONFIG);
+    assert_eq!(fs::read_to_string(dir.join("TraderConfig.txt.bak.1")).unwrap(), CONFIG.replace("AKM", "AK74"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_file_that_fails_to_parse_gets_no_backup() {
+    let dir = common::temp_dir("backup_malformed");
+    let file = dir.join("Malformed.txt");
+    fs::copy("tests/fixtures/Malformed.txt", &file).unwrap();
+
+    // --validate needs the whole file parsed first, without it the file is streamed
+    for args in [&["--in-place", "--backup"][..], &["--in-place", "--backup", "--validate"]] {
+        common::formatter().args(args).arg(&file).assert().code(1);
+    }
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files, ["Malformed.txt"]);
+}
+
+#[test]
+fn an_unchanged_file_gets_no_backup() {
+    let dir = common::temp_dir("backup_unchanged");
+    let file = dir.join("TraderConfig.txt");
+    fs::write(&file, FORMATTED).unwrap();
+
+    // Streamed and formatted as a whole
+    for args in [&["--in-place", "--backup"][..], &["--in-place", "--backup", "--validate"]] {
+        common::formatter().args(args).arg(&file).assert().success();
+        common::formatter().args(args).arg(&file).assert().success();
+    }
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files, ["TraderConfig.txt"]);
+}
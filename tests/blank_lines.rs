@@ -0,0 +1,12 @@
+use trader_config_formatter::{format, format_with, parse, FormatOptions};
+
+#[test]
+fn preserved_blank_lines_keep_traders_grouped() {
+    let tokens = parse("<Trader> Boris\n<Trader> Ivan\n\n\n<Trader> Sasha\n\n<Trader> Anna\n").unwrap();
+    let opts = FormatOptions { preserve_blank_lines: true, ..FormatOptions::default() };
+    assert_eq!(
+        format_with(&tokens, &opts),
+        "<Trader> Boris\n<Trader> Ivan\n\n<Trader> Sasha\n\n<Trader> Anna\n"
+    );
+    assert_eq!(format(&tokens), "<Trader> Boris\n\n<Trader> Ivan\n\n<Trader> Sasha\n\n<Trader> Anna\n");
+}
@@ -0,0 +1,34 @@
+use trader_config_formatter::{format, parse, CategoryItemToken, ParseError, Token, TraderCategoryToken};
+
+#[test]
+fn block_comments_inside_a_category_are_kept_whole() {
+    let config = "\
+<Trader> Boris
+    <Category> Weapons
+        /* rifles */
+        /* first
+           second */
+        AKM, *, 1000, 500
+";
+    let tokens = parse(config).unwrap();
+    let Token::Trader(boris) = &tokens[0] else { panic!("expected a trader") };
+    let TraderCategoryToken::TraderCategory(weapons) = &boris.categories[0] else { panic!("expected a category") };
+    match weapons.items.as_slice() {
+        [
+            CategoryItemToken::Comment(single),
+            CategoryItemToken::Comment(multi),
+            CategoryItemToken::CategoryItem(item),
+        ] => {
+            assert_eq!(single.text, " rifles ");
+            assert_eq!(multi.text, " first\n           second ");
+            assert_eq!(item.class, "AKM");
+        },
+        other => panic!("unexpected items {:?}", other),
+    }
+    let formatted = format(&tokens);
+    assert!(formatted.contains("/* rifles */\n"), "{}", formatted);
+    assert!(formatted.contains("/* first\n           second */\n"), "{}", formatted);
+
+    let err = parse("<Trader> Boris\n    <Category> Weapons\n        /* a /* b */ */\n").unwrap_err();
+    assert!(matches!(err, ParseError::NestedComment { location } if (location.line, location.column) == (3, 14)));
+}
@@ -0,0 +1,11 @@
+use trader_config_formatter::{parse, Token};
+
+#[test]
+fn a_leading_byte_order_mark_is_skipped() {
+    let tokens = parse("\u{FEFF}<OpenFile> TraderConfig.txt\n<FileEnd>\n").unwrap();
+    assert!(matches!(tokens.as_slice(), [Token::OpenFile(o), Token::FileEnd(_)] if o.0.text == "TraderConfig.txt"));
+
+    // Only at the very start, further along it's part of the text
+    let tokens = parse("<OpenFile> \u{FEFF}TraderConfig.txt\n").unwrap();
+    assert!(matches!(tokens.as_slice(), [Token::OpenFile(o)] if o.0.text == "\u{FEFF}TraderConfig.txt"));
+}
@@ -0,0 +1,65 @@
+use trader_config_formatter::{format, parse, sort_items};
+
+mod common;
+
+const FIRST: &str = "\
+<CurrencyName> Rubles
+    <Currency> MoneyRuble100, 100
+    <Currency> MoneyRuble1, 1
+
+<Trader> Zed
+    <Category> Rifles
+        // popular
+        M4A1, *, 2000, 1000
+        AKM, *, 1000.0, 500
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<Trader> Anna
+    <Category> Food
+        Pear, *, 5, 1
+        Apple, *, 05, 2
+<FileEnd>
+";
+
+const SECOND: &str = "<CurrencyName>   Rubles\r\n\t<Currency> MoneyRuble100,100\r\n\t<Currency> MoneyRuble1,1\r\n\
+\r\n\r\n<Trader> Anna\r\n<Category> Food\r\nApple,*,5,2\r\nPear , * , 5 , 1\r\n\
+<Trader> Zed\r\n<Category> Ammo\r\nAmmo_762x39,*,10,5\r\n\
+<Category> Rifles\r\nAKM,*,1000,500\r\n// popular\r\nM4A1,*,2000,1000\r\n<FileEnd>\r\n";
+
+fn canonical(config: &str) -> String {
+    common::stdout(common::run(&["--canonical", "-"], config))
+}
+
+#[test]
+fn equivalent_configs_are_written_byte_for_byte_the_same() {
+    let first = canonical(FIRST);
+    assert_eq!(canonical(SECOND), first);
+    assert_eq!(canonical(&first), first);
+
+    let traders: Vec<&str> = first.lines().filter(|l| l.starts_with("<Trader>")).collect();
+    assert_eq!(traders, ["<Trader> Anna", "<Trader> Zed"]);
+    assert!(first.contains("<Category> Ammo\n"), "{}", first);
+    assert!(!first.contains('\r'));
+    let rifles = &first[first.find("<Category> Rifles").unwrap()..];
+    let positions: Vec<usize> = ["AKM", "// popular", "M4A1"].iter().map(|s| rifles.find(s).unwrap()).collect();
+    assert!(positions.windows(2).all(|p| p[0] < p[1]), "{}", rifles);
+    assert!(rifles[positions[0]..].lines().next().unwrap().contains("1000"), "{}", rifles);
+}
+
+#[test]
+fn percentage_prices_are_written_the_same_way_too() {
+    let first = canonical("<Trader> Boris\n    <Category> Weapons\n        M4A1, *, 20%, 10%\n        AKM, *, 10%, 5%\n");
+    let second = canonical("<Trader> Boris\n<Category> Weapons\nAKM,*,010 %,5%\nM4A1,*,20%,10.0%\n");
+    assert_eq!(second, first);
+    assert!(first.contains("10%,") && first.contains("5%\n"), "{}", first);
+}
+
+#[test]
+fn sorting_items_orders_them_by_class_and_keeps_comments_with_the_next_item() {
+    let mut tokens = parse(FIRST).unwrap();
+    sort_items(&mut tokens);
+    let formatted = format(&tokens);
+    let classes: Vec<&str> = tokens.items().map(|(_, _, item)| item.class.as_str()).collect();
+    assert_eq!(classes, ["AKM", "M4A1", "Ammo_762x39", "Apple", "Pear"]);
+    assert!(formatted.contains("// popular\n                M4A1"), "{}", formatted);
+}
@@ -0,0 +1,137 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+mod common;
+
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+
+#[test]
+fn valid_file_is_printed_formatted() {
+    common::formatter()
+        .arg("tests/fixtures/TraderConfig.txt")
+        .assert()
+        .success()
+        .stdout(FORMATTED)
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn malformed_file_fails_with_the_error_location() {
+    common::formatter()
+        .arg("tests/fixtures/Malformed.txt")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Error processing file tests/fixtures/Malformed.txt: Category item at 5:9 has 2 values instead of 4 or 5",
+        ))
+        .stderr(predicate::str::contains("probably a missing comma: M4A1 *, 2000 1000"));
+}
+
+#[test]
+fn malformed_file_prints_what_parsed_before_the_error() {
+    // Stdout is streamed, the header comment is written before the broken trader is reached. Only
+    // --output and --in-place hold the file back until it parsed.
+    common::formatter()
+        .arg("tests/fixtures/Malformed.txt")
+        .assert()
+        .code(1)
+        .stdout("// Trader config with an item missing its commas, used by the CLI tests\n");
+}
+
+#[test]
+fn missing_path_fails_without_output() {
+    common::formatter()
+        .arg("tests/fixtures/Missing.txt")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("File not found: tests/fixtures/Missing.txt"));
+}
+
+#[test]
+fn directory_path_fails_with_its_own_message() {
+    common::formatter()
+        .arg("tests/fixtures")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("Path is a directory, not a file: tests/fixtures"));
+}
+
+#[test]
+fn check_reports_unformatted_files() {
+    common::formatter()
+        .args(["--check", "tests/fixtures/TraderConfig.formatted.txt"])
+        .assert()
+        .success();
+    common::formatter()
+        .args(["--check", "tests/fixtures/TraderConfig.txt"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn check_reports_a_changed_final_line_break() {
+    common::formatter().args(["--check", "-"]).write_stdin(FORMATTED).assert().success();
+    for contents in [FORMATTED.trim_end().to_string(), format!("{}\n", FORMATTED)] {
+        common::formatter().args(["--check", "-"]).write_stdin(contents).assert().code(1);
+    }
+}
+
+#[test]
+fn warnings_only_fail_with_fail_on_warnings() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n<FileEnd>\n";
+    common::formatter()
+        .args(["--lint", "--max-line-width", "40", "-"])
+        .write_stdin(config)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning in file -: Boris > Weapons > AKM: line is"));
+    common::formatter()
+        .args(["--lint", "--max-line-width", "40", "--fail-on-warnings", "-"])
+        .write_stdin(config)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Warning in file -: Boris > Weapons > AKM: line is"));
+    common::formatter()
+        .args(["--lint", "--fail-on-warnings", "-"])
+        .write_stdin(config)
+        .assert()
+        .success();
+}
+
+#[test]
+fn economy_check_warns_about_items_resold_for_a_profit() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 500, 1000\n        M4A1, *, 2000, 1000\n<FileEnd>\n";
+    common::formatter()
+        .args(["--economy-check", "-"])
+        .write_stdin(config)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning in file -: Boris > Weapons > AKM: sells for 1000"))
+        .stderr(predicate::str::contains("M4A1").not());
+    common::formatter().args(["-"]).write_stdin(config).assert().success().stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn in_place_leaves_an_existing_tmp_file_alone() {
+    let dir = common::temp_dir("tmp_file");
+    let config = dir.join("TraderConfig.txt");
+    fs::write(dir.join("TraderConfig.txt.tmp"), "my notes").unwrap();
+
+    // --validate needs the whole file parsed first, the formatted output is written as a String
+    for args in [&["--in-place"][..], &["--in-place", "--validate"]] {
+        fs::copy("tests/fixtures/TraderConfig.txt", &config).unwrap();
+        common::formatter().arg(&config).args(args).assert().success();
+        let formatted = fs::read_to_string(&config).unwrap();
+        let notes = fs::read_to_string(dir.join("TraderConfig.txt.tmp")).unwrap();
+        let files = fs::read_dir(&dir).unwrap().count();
+
+        assert_eq!(formatted, FORMATTED, "{:?}", args);
+        assert_eq!(notes, "my notes");
+        assert_eq!(files, 2, "{:?}", args);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
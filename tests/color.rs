@@ -0,0 +1,25 @@
+mod common;
+
+// NO_COLOR set where the tests run would hide what --color does
+fn errors(args: &[&str]) -> String {
+    common::stderr(common::formatter().args(args).env_remove("NO_COLOR").output().unwrap())
+}
+
+#[test]
+fn errors_are_plain_with_color_never() {
+    let err = errors(&["--color", "never", "tests/fixtures/missing.txt"]);
+    assert!(err.contains("Error processing file tests/fixtures/missing.txt"));
+    assert!(!err.contains('\x1b'));
+}
+
+#[test]
+fn errors_are_red_with_color_always() {
+    let err = errors(&["--color", "always", "tests/fixtures/missing.txt"]);
+    assert!(err.contains("\x1b[31mError processing file"));
+}
+
+#[test]
+fn errors_are_plain_when_piped() {
+    let err = errors(&["tests/fixtures/missing.txt"]);
+    assert!(!err.contains('\x1b'));
+}
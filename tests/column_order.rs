@@ -0,0 +1,38 @@
+use trader_config_formatter::{
+    format, format_with, items, parse_with, ColumnOrder, FormatOptions, ItemField, ParseOptions,
+};
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500 // gun
+        M4A1, M, 2000, 1000, 3
+<FileEnd>
+";
+
+#[test]
+fn column_order_is_parsed_from_field_names() {
+    let order: ColumnOrder = "class, buy_value,sell_value,amount".parse().unwrap();
+    assert_eq!(order.0, [ItemField::Class, ItemField::BuyValue, ItemField::SellValue, ItemField::Amount]);
+    assert_eq!(order.to_string(), "class,buy_value,sell_value,amount");
+
+    assert!("class,amount,buy_value".parse::<ColumnOrder>().is_err());
+    assert!("class,amount,amount,sell_value".parse::<ColumnOrder>().is_err());
+    assert!("class,amount,buy,sell_value".parse::<ColumnOrder>().is_err());
+}
+
+#[test]
+fn reordered_columns_reparse_with_the_matching_input_order() {
+    let order: ColumnOrder = "class,buy_value,sell_value,amount".parse().unwrap();
+    let tokens = parse_with(CONFIG, &ParseOptions::default()).unwrap();
+    let reordered = format_with(&tokens, &FormatOptions { column_order: order, compact: true, ..FormatOptions::default() });
+    assert!(reordered.contains("AKM, 1000, 500, * // gun"));
+    assert!(reordered.contains("M4A1, 2000, 1000, M, 3"));
+
+    let reparsed = parse_with(&reordered, &ParseOptions { column_order: order, ..ParseOptions::default() }).unwrap();
+    let values: Vec<[&str; 4]> = items(&reparsed)
+        .map(|(_, _, i)| [i.class.as_str(), i.amount.as_str(), i.buy_value.as_str(), i.sell_value.as_str()])
+        .collect();
+    assert_eq!(values, [["AKM", "*", "1000", "500"], ["M4A1", "M", "2000", "1000"]]);
+    assert_eq!(format(&reparsed), format(&tokens));
+}
@@ -0,0 +1,93 @@
+use trader_config_formatter::{
+    format, format_with, items, parse, CategoryItemToken, CommentStyle, FormatOptions, Indent, Token, TraderCategoryToken,
+};
+
+const CONFIG: &str = "\
+# hash header
+; semicolon header
+// slash header
+<Trader> Boris
+    <Category> Weapons
+        # hash item
+        AKM, *, 1000, 500
+        ; semicolon item
+        Item#1, *, 10, 5 // slash item
+<FileEnd>
+";
+
+#[test]
+fn each_comment_style_is_parsed() {
+    let tokens = parse(CONFIG).unwrap();
+    let styles: Vec<CommentStyle> = tokens.iter()
+        .filter_map(|t| match t {
+            Token::Comment(c) => Some(c.style),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(styles, [CommentStyle::Hash, CommentStyle::Semicolon, CommentStyle::Line]);
+
+    let trader = tokens.iter().find_map(|t| match t {
+        Token::Trader(t) => Some(t),
+        _ => None,
+    }).unwrap();
+    let item_comments: Vec<(CommentStyle, &str)> = match &trader.categories[0] {
+        TraderCategoryToken::TraderCategory(c) => c.items.iter()
+            .filter_map(|i| match i {
+                CategoryItemToken::Comment(c) => Some((c.style, c.text.as_str())),
+                _ => None,
+            })
+            .collect(),
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(item_comments, [(CommentStyle::Hash, "hash item"), (CommentStyle::Semicolon, "semicolon item")]);
+}
+
+#[test]
+fn hash_inside_a_value_is_not_a_comment() {
+    let tokens = parse(CONFIG).unwrap();
+    let classes: Vec<&str> = items(&tokens).map(|(_, _, i)| i.class.as_str()).collect();
+    assert_eq!(classes, ["AKM", "Item#1"]);
+}
+
+#[test]
+fn each_comment_style_round_trips() {
+    let formatted = format(&parse(CONFIG).unwrap());
+    for line in ["# hash header", "; semicolon header", "// slash header", "# hash item", "; semicolon item", "// slash item"] {
+        assert!(formatted.contains(line), "{} missing from\n{}", line, formatted);
+    }
+    assert_eq!(format(&parse(&formatted).unwrap()), formatted);
+}
+
+#[test]
+fn classes_starting_with_a_comment_prefix_are_quoted() {
+    let tokens = parse("<Trader> Boris\n    <Category> Weapons\n        \"#1\", *, 10, 5\n<FileEnd>\n").unwrap();
+    let formatted = format(&tokens);
+    assert!(formatted.contains("\"#1\","));
+    let classes: Vec<String> = items(&parse(&formatted).unwrap()).map(|(_, _, i)| i.class.clone()).collect();
+    assert_eq!(classes, ["#1"]);
+}
+
+#[test]
+fn comment_style_rewrites_comments_on_their_own_line() {
+    let opts = FormatOptions { comment_style: Some(CommentStyle::Line), ..FormatOptions::default() };
+    let formatted = format_with(&parse(CONFIG).unwrap(), &opts);
+    for line in ["// hash header", "// semicolon header", "// slash header", "// hash item", "// semicolon item"] {
+        assert!(formatted.contains(line), "{} missing from\n{}", line, formatted);
+    }
+    assert!(!formatted.lines().any(|l| l.trim_start().starts_with(['#', ';'])), "{}", formatted);
+}
+
+#[test]
+fn comments_after_values_and_block_comments_keep_their_style() {
+    let contents = "<Trader> Boris\n    <Category> Weapons\n        /* block */\n        AKM, *, 10, 5 // rifle\n<FileEnd>\n";
+    let opts = FormatOptions {
+        comment_style: Some(CommentStyle::Hash),
+        compact: true,
+        indent: Indent::Spaces(2),
+        ..FormatOptions::default()
+    };
+    assert_eq!(
+        format_with(&parse(contents).unwrap(), &opts),
+        "<Trader> Boris\n  <Category> Weapons\n    /* block */\n    AKM, *, 10, 5 // rifle\n\n<FileEnd>\n"
+    );
+}
@@ -0,0 +1,32 @@
+use trader_config_formatter::{parse, CategoryItemToken, Token, TraderCategoryToken};
+
+#[test]
+fn consecutive_comment_lines_are_each_kept() {
+    let config = "\
+// first
+// second
+<Trader> Boris
+    <Category> Weapons
+        // rifles
+        // cheap ones
+        AKM/S, *, 1000, 500
+";
+    let tokens = parse(config).unwrap();
+    let [Token::Comment(first), Token::Comment(second), Token::Trader(boris)] = tokens.as_slice() else {
+        panic!("unexpected tokens {:?}", tokens)
+    };
+    assert_eq!([first.text.as_str(), second.text.as_str()], ["first", "second"]);
+
+    let TraderCategoryToken::TraderCategory(weapons) = &boris.categories[0] else { panic!("expected a category") };
+    match weapons.items.as_slice() {
+        [
+            CategoryItemToken::Comment(rifles),
+            CategoryItemToken::Comment(cheap),
+            CategoryItemToken::CategoryItem(item),
+        ] => {
+            assert_eq!([rifles.text.as_str(), cheap.text.as_str()], ["rifles", "cheap ones"]);
+            assert_eq!(item.class, "AKM/S");
+        },
+        other => panic!("unexpected items {:?}", other),
+    }
+}
@@ -0,0 +1,70 @@
+// Each test crate uses only some of these helpers
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Output;
+
+use assert_cmd::Command;
+
+// The formatter binary, tests add the arguments and input
+pub fn formatter() -> Command {
+    Command::cargo_bin("trader_config_formatter").unwrap()
+}
+
+// Runs the formatter with the given arguments and input on stdin
+pub fn run(args: &[&str], input: impl Into<Vec<u8>>) -> Output {
+    formatter().args(args).write_stdin(input).output().unwrap()
+}
+
+// What a successful run printed
+pub fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// The errors of a run that failed
+pub fn stderr(output: Output) -> String {
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+// An empty directory for one test, named after it and the process so parallel runs don't share it
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("trader_config_formatter_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// A config with the given number of traders, categories per trader and items per category, with
+// a comment every few items so comment handling is part of the work
+pub fn synthetic_config(traders: usize, categories: usize, items: usize) -> String {
+    let mut config = String::from("<OpenFile> Synthetic.txt // generated\n\n");
+    config.push_str("<CurrencyName> Rubles\n");
+    for value in [100, 50, 10, 5, 1] {
+        config.push_str(&format!("    <Currency> MoneyRuble{}, {}\n", value, value));
+    }
+    for t in 0..traders {
+        config.push_str(&format!("\n<Trader> Trader{}\n", t));
+        for c in 0..categories {
+            config.push_str(&format!("    <Category> Category{}\n", c));
+            for i in 0..items {
+                let comment = if i % 5 == 0 { " // every fifth item" } else { "" };
+                config.push_str(&format!(
+                    "        Item_{}_{}_{}, *, {}, {}{}\n",
+                    t, c, i, (i + 1) * 10, (i + 1) * 5, comment
+                ));
+            }
+        }
+    }
+    config.push_str("<FileEnd>\n");
+    config
+}
+
+// A single currency line with the given number of values and no line break after it, as a
+// machine-generated config could have
+pub fn long_line_config(values: usize) -> String {
+    let values: Vec<String> = (0..values).map(|v| format!("MoneyRuble{}", v)).collect();
+    format!("<CurrencyName> Rubles\n    <Currency> {}", values.join(", "))
+}
@@ -0,0 +1,84 @@
+use std::fs;
+use trader_config_formatter::{compare, parse, Change, ChangeKind};
+
+mod common;
+
+const OLD: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+        M4A1, *, 2000, 1000
+<FileEnd>
+";
+
+fn changes(new: &str) -> Vec<Change> {
+    compare(&parse(OLD).unwrap(), &parse(new).unwrap())
+}
+
+fn change(class: &str, kind: ChangeKind) -> Change {
+    Change { trader: "Boris".into(), category: "Weapons".into(), class: class.into(), kind }
+}
+
+#[test]
+fn formatting_differences_are_not_changes() {
+    assert!(changes("<Trader>   Boris\n<Category> Weapons // guns\nAKM,*,1000,500\nM4A1 , * , 2000 , 1000\n<FileEnd>\n")
+        .is_empty());
+}
+
+#[test]
+fn reports_an_added_item() {
+    let new = OLD.replace("        M4A1", "        SVD, *, 5000, 2500\n        M4A1");
+    assert_eq!(changes(&new), [change("SVD", ChangeKind::Added)]);
+}
+
+#[test]
+fn reports_a_removed_item() {
+    let new = OLD.replace("        AKM, *, 1000, 500\n", "");
+    assert_eq!(changes(&new), [change("AKM", ChangeKind::Removed)]);
+}
+
+#[test]
+fn reports_a_price_change() {
+    let new = OLD.replace("AKM, *, 1000, 500", "AKM, *, 1200, 500");
+    let expected = change("AKM", ChangeKind::Changed { field: "buy value", old: "1000".into(), new: "1200".into() });
+    let changes = changes(&new);
+    assert_eq!(changes, [expected]);
+    assert_eq!(changes[0].to_string(), "~ Boris > Weapons > AKM: buy value 1000 -> 1200");
+}
+
+#[test]
+fn an_item_moved_to_another_category_is_removed_and_added() {
+    let new = OLD.replace("        AKM, *, 1000, 500\n", "")
+        .replace("<FileEnd>", "    <Category> Rifles\n        AKM, *, 1000, 500\n<FileEnd>");
+    let moved = Change { category: "Rifles".into(), ..change("AKM", ChangeKind::Added) };
+    assert_eq!(changes(&new), [change("AKM", ChangeKind::Removed), moved]);
+}
+
+#[test]
+fn compare_subcommand_prints_the_changes_and_exits_with_1() {
+    let dir = common::temp_dir("compare");
+    let old = dir.join("Old.txt");
+    let new = dir.join("New.txt");
+    fs::write(&old, OLD).unwrap();
+    fs::write(&new, OLD.replace("M4A1, *, 2000, 1000", "M4A1, *, 2000, 900\n        SVD, *, 5000, 2500")).unwrap();
+
+    let output = common::formatter()
+        .arg("compare")
+        .args([&old, &new])
+        .output()
+        .unwrap();
+    let json = common::formatter()
+        .args(["compare", "--format", "json"])
+        .args([&old, &old])
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "~ Boris > Weapons > M4A1: sell value 1000 -> 900\n+ Boris > Weapons > SVD\n"
+    );
+    assert_eq!(json.status.code(), Some(0));
+    assert_eq!(String::from_utf8(json.stdout).unwrap(), "[]\n");
+}
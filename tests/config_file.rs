@@ -0,0 +1,91 @@
+use predicates::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+
+const CONFIG: &str = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n<FileEnd>\n";
+
+// A project directory holding the given .traderfmt.toml, with the config one directory below it
+// so the file is found in a parent directory
+fn project(name: &str, toml: &str) -> (PathBuf, PathBuf) {
+    let dir = common::temp_dir(&format!("config_file_{}", name));
+    let nested = dir.join("Trader");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(dir.join(".traderfmt.toml"), toml).unwrap();
+    fs::write(nested.join("TraderConfig.txt"), CONFIG).unwrap();
+    (dir, nested)
+}
+
+#[test]
+fn options_are_read_from_the_nearest_config_file() {
+    let (dir, nested) = project("read", "no-pad = true\nindent-char = \"tab\"\n");
+    let output = common::formatter().current_dir(&nested).arg("TraderConfig.txt").output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "<Trader> Boris\n\t<Category> Weapons\n\t\tAKM, *, 1000, 500\n\n<FileEnd>\n"
+    );
+}
+
+#[test]
+fn command_line_flags_override_the_config_file() {
+    let (dir, nested) = project("override", "no-pad = true\nindent-size = 2\nmax-amount = 10\nvalidate = true\n");
+    let overridden = common::formatter().current_dir(&nested).args(["--indent-size", "3", "TraderConfig.txt"]).output().unwrap();
+    fs::write(nested.join("Stacks.txt"), CONFIG.replace("AKM, *", "AKM, 50")).unwrap();
+    let rejected = common::formatter().current_dir(&nested).arg("Stacks.txt").output().unwrap();
+    let accepted = common::formatter().current_dir(&nested).args(["--max-amount", "100", "Stacks.txt"]).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(overridden.status.success(), "{}", String::from_utf8_lossy(&overridden.stderr));
+    let stdout = String::from_utf8(overridden.stdout).unwrap();
+    assert!(stdout.starts_with("<Trader> Boris\n   <Category> Weapons\n      AKM"), "{}", stdout);
+    assert_eq!(rejected.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&rejected.stderr).contains("amount 50 is more than the maximum of 10"));
+    // The file's validate = true still applies next to --max-amount
+    assert!(accepted.status.success(), "{}", String::from_utf8_lossy(&accepted.stderr));
+}
+
+#[test]
+fn keys_conflicting_with_a_command_line_flag_are_skipped() {
+    let (dir, nested) = project("conflict", "width = 30\nalign-comments = true\n");
+    let no_pad = common::formatter().current_dir(&nested).args(["--no-pad", "TraderConfig.txt"]).output().unwrap();
+    let column = common::formatter().current_dir(&nested).args(["--comment-column", "40", "TraderConfig.txt"]).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(no_pad.status.success(), "{}", String::from_utf8_lossy(&no_pad.stderr));
+    assert!(String::from_utf8(no_pad.stdout).unwrap().contains(" AKM, *, 1000, 500\n"));
+    assert!(column.status.success(), "{}", String::from_utf8_lossy(&column.stderr));
+}
+
+#[test]
+fn keys_a_subcommand_does_not_take_are_ignored_by_it() {
+    let (dir, nested) = project("subcommand", "indent-size = 2\nmax-line-width = 20\nlint = true\n");
+    common::formatter().current_dir(&nested)
+        .args(["validate", "TraderConfig.txt"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("line is"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unknown_keys_and_values_of_the_wrong_type_are_argument_errors() {
+    let (dir, nested) = project("unknown", "in-place = true\n");
+    common::formatter().current_dir(&nested)
+        .arg("TraderConfig.txt")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("unknown key in-place"));
+    fs::write(dir.join(".traderfmt.toml"), "width = [60]\n").unwrap();
+    common::formatter().current_dir(&nested)
+        .arg("TraderConfig.txt")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("width has to be a string, a number or a boolean, not array"));
+    fs::write(dir.join(".traderfmt.toml"), "indent-char = \"dot\"\n").unwrap();
+    common::formatter().current_dir(&nested).arg("TraderConfig.txt").assert().code(4);
+    fs::remove_dir_all(&dir).unwrap();
+}
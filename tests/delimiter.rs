@@ -0,0 +1,38 @@
+use trader_config_formatter::{format_with, items, parse, parse_with, FormatOptions, ParseOptions};
+
+const CONFIG: &str = "<Trader> Boris\n\t<Category>\tWeapons\n\t\tAKM\t*\t1000\t500 // gun\n\t\tMag_AKM_30Rnd\tM\t50\t25\n<FileEnd>\n";
+
+fn tabs() -> (ParseOptions, FormatOptions) {
+    (
+        ParseOptions { delimiter: '\t', ..ParseOptions::default() },
+        FormatOptions { delimiter: '\t', ..FormatOptions::default() },
+    )
+}
+
+#[test]
+fn tab_delimited_category_is_parsed() {
+    let (parse_opts, _) = tabs();
+    let tokens = parse_with(CONFIG, &parse_opts).unwrap();
+    let values: Vec<[&str; 4]> = items(&tokens)
+        .map(|(_, c, i)| [c.name.text.as_str(), i.class.as_str(), i.amount.as_str(), i.sell_value.as_str()])
+        .collect();
+    assert_eq!(values, [["Weapons", "AKM", "*", "500"], ["Weapons", "Mag_AKM_30Rnd", "M", "25"]]);
+
+    // Commas are plain characters when values are tab delimited
+    assert!(parse(CONFIG).is_err());
+}
+
+#[test]
+fn tab_delimited_output_is_aligned_with_tabs() {
+    let (parse_opts, format_opts) = tabs();
+    let formatted = format_with(&parse_with(CONFIG, &parse_opts).unwrap(), &format_opts);
+    let lines: Vec<&str> = formatted.lines().filter(|l| l.contains("AKM")).collect();
+    assert_eq!(lines, [
+        "                AKM\t\t\t\t\t\t\t\t*\t\t1000\t\t500\t\t// gun",
+        "                Mag_AKM_30Rnd\t\t\t\t\t\t\tM\t\t50\t\t25",
+    ]);
+    assert_eq!(format_with(&parse_with(&formatted, &parse_opts).unwrap(), &format_opts), formatted);
+
+    let compact = format_with(&parse_with(CONFIG, &parse_opts).unwrap(), &FormatOptions { compact: true, ..format_opts });
+    assert!(compact.contains("AKM\t*\t1000\t500 // gun"));
+}
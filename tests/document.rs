@@ -0,0 +1,51 @@
+use trader_config_formatter::{format, parse};
+
+const CONFIG: &str = "\
+// header
+<CurrencyName> Rubles
+    <Currency> MoneyRuble1, 1 // one
+<Trader> Boris // trader comment
+    <Category> Weapons
+        AKM, *, 1000, 500 // rifle
+    /* between */
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<Trader> Ivan
+    <Category> Food
+        Apple, *, 5, 1
+<FileEnd>
+";
+
+#[test]
+fn traders_and_currency_names_are_listed_in_order() {
+    let document = parse(CONFIG).unwrap();
+    let traders: Vec<&str> = document.traders().map(|t| t.name.text.as_str()).collect();
+    assert_eq!(traders, ["Boris", "Ivan"]);
+    let currencies: Vec<&str> = document.currency_names().map(|c| c.name.text.as_str()).collect();
+    assert_eq!(currencies, ["Rubles"]);
+}
+
+#[test]
+fn comments_include_trailing_ones() {
+    let document = parse(CONFIG).unwrap();
+    let comments: Vec<&str> = document.comments().map(|c| c.text.trim()).collect();
+    assert_eq!(comments, ["header", "one", "trader comment", "rifle", "between"]);
+}
+
+#[test]
+fn find_trader_by_name() {
+    let mut document = parse(CONFIG).unwrap();
+    assert_eq!(document.find_trader("Ivan").unwrap().categories.len(), 1);
+    assert!(document.find_trader("ivan").is_none());
+
+    document.find_trader_mut("Ivan").unwrap().name.text = "Sasha".to_string();
+    assert!(document.find_trader("Sasha").is_some());
+    let classes: Vec<&str> = document.items().map(|(_, _, i)| i.class.as_str()).collect();
+    assert_eq!(classes, ["AKM", "Ammo_762x39", "Apple"]);
+}
+
+#[test]
+fn display_formats_the_whole_file() {
+    let document = parse(CONFIG).unwrap();
+    assert_eq!(document.to_string(), format(&document));
+}
@@ -0,0 +1,58 @@
+use std::fs;
+
+mod common;
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+
+#[test]
+fn dry_run_writes_nothing() {
+    let dir = common::temp_dir("dry_run");
+    fs::write(dir.join("a.txt"), CONFIG).unwrap();
+    fs::write(dir.join("b.txt"), FORMATTED).unwrap();
+
+    let output = common::formatter()
+        .args(["--dry-run", "--in-place", "--recursive"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("Would rewrite {}", dir.join("a.txt").display())), "{}", stdout);
+    assert!(!stdout.contains("b.txt"));
+    assert!(stdout.ends_with("Would change 1 of 2 files\n"));
+    assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), CONFIG);
+    assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), FORMATTED);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dry_run_with_an_output_file_compares_against_that_file() {
+    let dir = common::temp_dir("dry_run_output");
+    let input = dir.join("input.txt");
+    let output = dir.join("output.txt");
+    fs::write(&input, CONFIG).unwrap();
+
+    let dry_run = || {
+        let result = common::formatter()
+            .arg("--dry-run")
+            .arg(&input)
+            .arg("-o")
+            .arg(&output)
+            .output()
+            .unwrap();
+        (result.status.code(), String::from_utf8(result.stdout).unwrap())
+    };
+
+    let (missing_code, missing) = dry_run();
+    fs::write(&output, FORMATTED).unwrap();
+    let (same_code, same) = dry_run();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(missing_code, Some(1));
+    assert!(missing.contains(&format!("Would write {}", output.display())), "{}", missing);
+    assert_eq!(same_code, Some(0));
+    assert!(!same.contains("Would write"), "{}", same);
+}
@@ -0,0 +1,58 @@
+use trader_config_formatter::{duplicates, parse};
+
+mod common;
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+        M4A1, *, 2000, 1000
+    <Category> Sale
+        AKM, *, 800, 400
+<Trader> Ivan
+    <Category> Weapons
+        M4A1, *, 2000, 1000
+        AKM, *, 1000, 500
+        SVD, *, 3000, 1500
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+        Ammo_556x45, *, 10, 5
+        Ammo_762x39, *, 10, 5
+<FileEnd>
+";
+
+#[test]
+fn counts_the_listings_of_each_repeated_class() {
+    let counts: Vec<(String, usize)> = duplicates(&parse(CONFIG).unwrap())
+        .into_iter()
+        .map(|d| (d.class, d.listings.len()))
+        .collect();
+    assert_eq!(counts, [("AKM".to_string(), 3), ("M4A1".to_string(), 2), ("Ammo_762x39".to_string(), 2)]);
+}
+
+#[test]
+fn listings_name_the_trader_category_and_line() {
+    let duplicates = duplicates(&parse(CONFIG).unwrap());
+    let listings: Vec<(&str, &str, usize)> = duplicates[0].listings.iter()
+        .map(|l| (l.trader.as_str(), l.category.as_str(), l.location.line))
+        .collect();
+    assert_eq!(listings, [("Boris", "Weapons", 3), ("Boris", "Sale", 6), ("Ivan", "Weapons", 10)]);
+}
+
+#[test]
+fn count_duplicates_prints_the_top_classes() {
+    let dir = common::temp_dir("duplicates");
+    let file = dir.join("TraderConfig.txt");
+    std::fs::write(&file, CONFIG).unwrap();
+
+    let assert = common::formatter()
+        .args(["--count-duplicates", "--top", "2"])
+        .arg(&file)
+        .assert();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert.success().stdout(
+        "AKM      3  Boris > Weapons (3:9), Boris > Sale (6:9), Ivan > Weapons (10:9)\n\
+         M4A1     2  Boris > Weapons (4:9), Ivan > Weapons (9:9)\n",
+    );
+}
@@ -0,0 +1,37 @@
+use serde::Deserialize;
+use trader_config_formatter::Diagnostic;
+
+mod common;
+
+#[derive(Deserialize)]
+struct FileDiagnostic {
+    file: String,
+    #[serde(flatten)]
+    diagnostic: Diagnostic,
+}
+
+fn diagnostics(args: &[&str], input: &str) -> Vec<FileDiagnostic> {
+    serde_json::from_str(&common::stderr(common::run(args, input))).unwrap()
+}
+
+#[test]
+fn parse_errors_are_reported_as_json() {
+    let errors = diagnostics(&["--error-format", "json", "-"], "<Trader> Boris\n    <Categ\n");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].file, "-");
+    assert_eq!(errors[0].diagnostic, Diagnostic {
+        kind: "unclosed_tag".to_string(),
+        message: "Tag <Categ> at 2:5 isn't closed before the end of the line".to_string(),
+        line: Some(2),
+        column: Some(5),
+        offset: Some(19),
+    });
+}
+
+#[test]
+fn each_violation_is_its_own_diagnostic() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n        AKM, *, 900, 400\n        M4A1, *, abc, 1000\n<FileEnd>\n";
+    let errors = diagnostics(&["--error-format", "json", "--validate", "-"], config);
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|e| e.diagnostic.kind == "validation" && e.diagnostic.line.is_none()));
+}
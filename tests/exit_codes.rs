@@ -0,0 +1,40 @@
+mod common;
+
+// Bad arguments exit before the input is read, assert_cmd doesn't fail on the closed pipe
+fn exit_code(args: &[&str], input: &str) -> Option<i32> {
+    common::run(args, input)
+        .status
+        .code()
+}
+
+const VALID: &str = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n<FileEnd>\n";
+
+#[test]
+fn success_exits_with_0() {
+    assert_eq!(exit_code(&["-"], VALID), Some(0));
+}
+
+#[test]
+fn parse_error_exits_with_1() {
+    assert_eq!(exit_code(&["-"], "<Trader> Boris\n    <Categ\n"), Some(1));
+}
+
+#[test]
+fn validation_failure_exits_with_2() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, abc, 500\n<FileEnd>\n";
+    assert_eq!(exit_code(&["--validate", "-"], config), Some(2));
+}
+
+#[test]
+fn missing_file_exits_with_3() {
+    assert_eq!(exit_code(&["tests/fixtures/missing.txt"], ""), Some(3));
+}
+
+#[test]
+fn bad_arguments_exit_with_4() {
+    assert_eq!(exit_code(&["--width", "wide", "-"], VALID), Some(4));
+    assert_eq!(exit_code(&["--check", "--dry-run", "-"], VALID), Some(4));
+    assert_eq!(exit_code(&["--only-trader", "Nobody", "-"], VALID), Some(4));
+    assert_eq!(exit_code(&["--in-place", "-"], VALID), Some(4));
+    assert_eq!(exit_code(&["--help"], ""), Some(0));
+}
@@ -0,0 +1,20 @@
+use trader_config_formatter::{parse, to_csv, LineEnding};
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const EXPECTED: &str = include_str!("fixtures/TraderConfig.csv");
+
+#[test]
+fn exported_csv_matches_the_fixture() {
+    assert_eq!(to_csv(&parse(CONFIG).unwrap(), LineEnding::Lf), EXPECTED);
+}
+
+#[test]
+fn values_with_commas_and_quotes_are_quoted() {
+    let config = "<Trader> Boris\n    <Category> Food, \"Fresh\"\n        Apple, *, 5, 1 // sweet, red\n<FileEnd>\n";
+    let csv = to_csv(&parse(config).unwrap(), LineEnding::CrLf);
+    assert_eq!(
+        csv.lines().nth(1),
+        Some("Boris,\"Food, \"\"Fresh\"\"\",Apple,*,5,1,,\"sweet, red\"")
+    );
+    assert!(csv.ends_with("\r\n"));
+}
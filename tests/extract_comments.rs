@@ -0,0 +1,53 @@
+use trader_config_formatter::parse;
+
+mod common;
+
+const FIXTURE: &str = "tests/fixtures/TraderConfig.txt";
+
+#[test]
+fn lists_every_comment_of_the_fixture_in_order() {
+    common::formatter()
+        .args(["--extract-comments", FIXTURE])
+        .assert()
+        .success()
+        .stdout(
+            "Trader config used by the integration tests\nmain file\nmoney\nsmall notes\nsmallest\nmain trader\ngun\n\
+             rifles\ntemporarily disabled\n           SVD, *, 5000, 2500\nammo below\n",
+        );
+}
+
+#[test]
+fn comment_locations_are_printed_before_each_comment() {
+    let output = common::formatter().args(["--extract-comments", "--comment-locations", FIXTURE]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "1:1: Trader config used by the integration tests");
+    assert_eq!(lines[1], "2:29: main file");
+    assert_eq!(lines.last(), Some(&"17:5: ammo below"));
+}
+
+#[test]
+fn comments_keep_the_location_they_were_parsed_at() {
+    let document = parse("<Trader> Boris // main\n    <Category> Weapons\n        // rifles\n        AKM, *, 1, 1 /* x */\n")
+        .unwrap();
+    let locations: Vec<(usize, usize)> = document.comments().map(|c| (c.location.line, c.location.column)).collect();
+    assert_eq!(locations, [(1, 16), (3, 9), (4, 22)]);
+}
+
+#[test]
+fn json_lists_line_column_and_text() {
+    let output = common::formatter().args(["--extract-comments", "--format", "json", FIXTURE]).output().unwrap();
+    let comments: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(comments[1], serde_json::json!({ "line": 2, "column": 29, "text": "main file" }));
+    assert_eq!(comments.as_array().unwrap().len(), 10);
+}
+
+#[test]
+fn block_and_line_comments_on_one_tag_line_are_each_listed() {
+    common::formatter()
+        .args(["--extract-comments", "-"])
+        .write_stdin("<Trader> Boris /* main */ // note\n    <Category> Weapons /* a */ /* b */ // c\n")
+        .assert()
+        .success()
+        .stdout("main\nnote\na\nb\nc\n");
+}
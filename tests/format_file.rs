@@ -0,0 +1,33 @@
+use trader_config_formatter::{format_file, format_file_with, FormatOptions, LineEnding};
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+const MULTI_TRADER: &str = include_str!("fixtures/MultiTrader.txt");
+const MULTI_TRADER_FORMATTED: &str = include_str!("fixtures/MultiTrader.formatted.txt");
+
+#[test]
+fn formatted_file_matches_the_fixture() {
+    assert_eq!(format_file(CONFIG).unwrap(), FORMATTED);
+}
+
+#[test]
+fn blocks_are_separated_by_exactly_one_blank_line() {
+    // The input has back to back traders as well as runs of blank lines
+    assert_eq!(format_file(MULTI_TRADER).unwrap(), MULTI_TRADER_FORMATTED);
+    assert!(!MULTI_TRADER_FORMATTED.contains("\n\n\n"));
+    assert!(MULTI_TRADER_FORMATTED.ends_with("\n<FileEnd>\n"));
+}
+
+#[test]
+fn formatted_file_uses_the_given_options() {
+    let opts = FormatOptions { line_ending: LineEnding::CrLf, ..FormatOptions::default() };
+    let formatted = format_file_with(CONFIG, &opts).unwrap();
+    // Block comments keep their own line breaks
+    assert_eq!(formatted.replace("\r\n", "\n"), FORMATTED);
+    assert!(formatted.starts_with("// Trader config used by the integration tests\r\n"));
+}
+
+#[test]
+fn parse_errors_are_returned() {
+    assert!(format_file("<Trader> Boris\n<Bogus>\n").is_err());
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use trader_config_formatter::{resolve_includes, ParseOptions};
+
+mod common;
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+
+fn write_gzip(path: &Path, contents: &str) {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(contents.as_bytes()).unwrap();
+    fs::write(path, encoder.finish().unwrap()).unwrap();
+}
+
+fn read_gzip(path: &Path) -> String {
+    let mut contents = String::new();
+    GzDecoder::new(fs::File::open(path).unwrap()).read_to_string(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn gzipped_configs_are_formatted_in_place_and_stay_compressed() {
+    let dir = common::temp_dir("gzip");
+    let archived = dir.join("TraderConfig.txt.gz");
+    write_gzip(&archived, CONFIG);
+    let renamed = dir.join("TraderConfig.bak");
+    write_gzip(&renamed, CONFIG);
+    let plain_output = dir.join("plain.txt");
+    let compressed_output = dir.join("compressed.txt.gz");
+
+    let in_place = common::formatter().arg("--in-place").arg(&archived).output().unwrap();
+    let sniffed = common::formatter().arg(&renamed).arg("--output").arg(&plain_output).output().unwrap();
+    let compressed = common::formatter()
+        .arg(&plain_output)
+        .arg("--output")
+        .arg(&compressed_output)
+        .output()
+        .unwrap();
+
+    let results = (read_gzip(&archived), fs::read_to_string(&plain_output), read_gzip(&compressed_output));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(in_place.status.success(), "{}", String::from_utf8_lossy(&in_place.stderr));
+    assert!(sniffed.status.success(), "{}", String::from_utf8_lossy(&sniffed.stderr));
+    assert!(compressed.status.success(), "{}", String::from_utf8_lossy(&compressed.stderr));
+    assert_eq!(results.0, FORMATTED);
+    assert_eq!(results.1.unwrap(), FORMATTED);
+    assert_eq!(results.2, FORMATTED);
+}
+
+#[test]
+fn compressed_files_without_a_gz_name_stay_compressed_in_place() {
+    let dir = common::temp_dir("gzip_in_place");
+    let renamed = dir.join("TraderConfig.txt");
+    write_gzip(&renamed, CONFIG);
+
+    let output = common::formatter().arg("--in-place").arg(&renamed).output().unwrap();
+    let written = fs::read(&renamed).unwrap();
+    let contents = read_gzip(&renamed);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(written.starts_with(&[0x1f, 0x8b]));
+    assert_eq!(contents, FORMATTED);
+}
+
+#[test]
+fn compressed_files_are_followed_through_open_file() {
+    let dir = common::temp_dir("gzip_includes");
+    fs::write(dir.join("TraderConfig.txt"), "<OpenFile> Archive.txt\n<FileEnd>\n").unwrap();
+    write_gzip(&dir.join("Archive.txt"), "<OpenFile> Weapons.txt\n<Trader> Boris\n<FileEnd>\n");
+    fs::write(dir.join("Weapons.txt"), "<Trader> Ivan\n<FileEnd>\n").unwrap();
+
+    let files = resolve_includes(&dir.join("TraderConfig.txt"), &ParseOptions::default());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files, [dir.join("TraderConfig.txt"), dir.join("Archive.txt"), dir.join("Weapons.txt")]);
+}
@@ -0,0 +1,35 @@
+use trader_config_formatter::{format, format_with, parse, to_json, FormatOptions, LineEnding};
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+
+fn assert_idempotent(contents: &str, opts: &FormatOptions) {
+    let first = format_with(&parse(contents).unwrap(), opts);
+    let second = format_with(&parse(&first).unwrap(), opts);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn formatting_a_formatted_file_is_a_no_op() {
+    let first = format(&parse(CONFIG).unwrap());
+    let second = format(&parse(&first).unwrap());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn formatting_is_idempotent_with_options() {
+    assert_idempotent(CONFIG, &FormatOptions { width: 20, ..FormatOptions::default() });
+    assert_idempotent(CONFIG, &FormatOptions { preserve_blank_lines: true, ..FormatOptions::default() });
+    assert_idempotent(
+        &CONFIG.replace('\n', "\r\n"),
+        &FormatOptions { line_ending: LineEnding::CrLf, ..FormatOptions::default() },
+    );
+}
+
+#[test]
+fn compact_output_reparses_to_the_same_tokens_as_padded_output() {
+    let padded = format(&parse(CONFIG).unwrap());
+    let compact = format_with(&parse(CONFIG).unwrap(), &FormatOptions { compact: true, ..FormatOptions::default() });
+    assert!(!compact.contains(",  "));
+    assert_eq!(to_json(&parse(&compact).unwrap()).unwrap(), to_json(&parse(&padded).unwrap()).unwrap());
+    assert_idempotent(CONFIG, &FormatOptions { compact: true, ..FormatOptions::default() });
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use trader_config_formatter::{resolve_includes, ParseOptions};
+
+mod common;
+
+const ROOT: &str = "<OpenFile> TraderConfig.txt\n<OpenFile> Weapons.txt\n<Trader> Boris\n<FileEnd>\n";
+// Names the root file again, which must not loop
+const WEAPONS: &str = "\
+<OpenFile> TraderConfig.txt
+<Trader> Ivan
+    <Category> Weapons
+        AKM,*,1000,500
+<FileEnd>
+";
+
+fn chain(name: &str) -> PathBuf {
+    let dir = common::temp_dir(name);
+    fs::write(dir.join("TraderConfig.txt"), ROOT).unwrap();
+    fs::write(dir.join("Weapons.txt"), WEAPONS).unwrap();
+    dir
+}
+
+#[test]
+fn open_file_references_are_followed_once() {
+    let dir = chain("includes");
+    let files = resolve_includes(&dir.join("TraderConfig.txt"), &ParseOptions::default());
+    assert_eq!(files, [dir.join("TraderConfig.txt"), dir.join("Weapons.txt")]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_files_are_listed_without_being_followed() {
+    let dir = chain("missing_include");
+    fs::remove_file(dir.join("Weapons.txt")).unwrap();
+    let files = resolve_includes(&dir.join("TraderConfig.txt"), &ParseOptions::default());
+    assert_eq!(files, [dir.join("TraderConfig.txt"), dir.join("Weapons.txt")]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn follow_includes_checks_every_file_of_the_chain() {
+    let dir = chain("follow_includes");
+    let output = common::formatter()
+        .args(["--follow-includes", "--dry-run"])
+        .arg(dir.join("TraderConfig.txt"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("Weapons.txt"), "{}", stdout);
+    assert!(stdout.contains("Would change 2 of 2 files"), "{}", stdout);
+}
@@ -0,0 +1,111 @@
+use trader_config_formatter::{format_with, parse, FormatOptions, Indent};
+
+mod common;
+
+const CONFIG: &str = "\
+<CurrencyName> Rubles
+    // notes
+    <Currency> MoneyRuble1, 1
+<Trader> Boris
+    // weapons first
+    <Category> Weapons
+        // rifles
+        AKM, *, 1000, 500
+    // ammo below
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<FileEnd>
+";
+
+fn leading_whitespace(formatted: &str) -> Vec<&str> {
+    formatted
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .collect()
+}
+
+#[test]
+fn nested_trader_with_a_two_space_indent() {
+    let opts = FormatOptions { indent: Indent::Spaces(2), width: 0, ..FormatOptions::default() };
+    let formatted = format_with(&parse(CONFIG).unwrap(), &opts);
+    assert_eq!(leading_whitespace(&formatted), ["", "  ", "  ", "", "  ", "  ", "    ", "    ", "    ", "  ", "    ", ""]);
+    assert!(formatted.contains("\n  <Category> Weapons\n    // rifles\n    AKM,"));
+}
+
+#[test]
+fn nested_trader_with_a_tab_indent() {
+    let opts = FormatOptions { indent: Indent::Tabs(1), ..FormatOptions::default() };
+    let formatted = format_with(&parse(CONFIG).unwrap(), &opts);
+    assert_eq!(leading_whitespace(&formatted), ["", "\t", "\t", "", "\t", "\t", "\t\t", "\t\t", "\t\t", "\t", "\t\t", ""]);
+}
+
+#[test]
+fn default_indent_keeps_the_legacy_layout() {
+    let formatted = format_with(&parse(CONFIG).unwrap(), &FormatOptions::default());
+    let eight = " ".repeat(8);
+    let sixteen = " ".repeat(16);
+    assert_eq!(
+        leading_whitespace(&formatted),
+        ["", &eight, &eight, "", "    ", "    ", &sixteen, &sixteen, &sixteen, "    ", &sixteen, ""]
+    );
+}
+
+#[test]
+fn comments_line_up_with_the_lines_they_annotate() {
+    let formatted = format_with(&parse(CONFIG).unwrap(), &FormatOptions::default());
+    let lines: Vec<&str> = formatted.lines().collect();
+    let indent_of = |text: &str| {
+        let line = lines.iter().find(|l| l.trim_start().starts_with(text)).unwrap();
+        &line[..line.len() - line.trim_start().len()]
+    };
+    assert_eq!(indent_of("// rifles"), indent_of("AKM,"));
+    assert_eq!(indent_of("// weapons first"), indent_of("<Category> Weapons"));
+    assert_eq!(indent_of("// notes"), indent_of("<Currency>"));
+    assert!(!formatted.contains('\t'));
+}
+
+fn format_stdin(args: &[&str]) -> String {
+    let output = common::formatter()
+        .args(args)
+        .arg("-")
+        .write_stdin(CONFIG)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn indent_size_sets_the_column_of_every_nesting_level() {
+    let formatted = format_stdin(&["--indent-size", "2", "--no-pad"]);
+    let columns: Vec<(usize, &str)> = formatted
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| (l.len() - l.trim_start().len(), l.trim_start()))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            (0, "<CurrencyName> Rubles"),
+            (2, "// notes"),
+            (2, "<Currency> MoneyRuble1, 1"),
+            (0, "<Trader> Boris"),
+            (2, "// weapons first"),
+            (2, "<Category> Weapons"),
+            (4, "// rifles"),
+            (4, "AKM, *, 1000, 500"),
+            (4, "// ammo below"),
+            (2, "<Category> Ammo"),
+            (4, "Ammo_762x39, *, 10, 5"),
+            (0, "<FileEnd>"),
+        ]
+    );
+}
+
+#[test]
+fn indent_char_tab_indents_one_tab_per_level() {
+    assert_eq!(leading_whitespace(&format_stdin(&["--indent-char", "tab"]))[6], "\t\t");
+    assert_eq!(leading_whitespace(&format_stdin(&["--indent-char", "tab", "--indent-size", "2"]))[6], "\t\t\t\t");
+    assert_eq!(leading_whitespace(&format_stdin(&["--indent-char", "space"]))[6], " ".repeat(8));
+}
@@ -0,0 +1,40 @@
+use serde_json::Value;
+use trader_config_formatter::{format, from_json, parse, to_json};
+
+mod common;
+
+const CONFIG: &str = "\
+<OpenFile> TraderConfig.txt
+// currencies
+<CurrencyName> Rubles
+    <Currency> MoneyRuble100, 100
+<Trader> Boris // main
+    <Category> Weapons
+        // rifles
+        AKM, *, 1000, 500 // rifle
+<FileEnd>
+";
+
+#[test]
+fn json_keeps_every_value_and_comment() {
+    let tokens = parse("<Trader> Boris // main\n    <Category> Weapons\n        AKM, *, 1000, 500 // rifle\n").unwrap();
+    let value: Value = serde_json::from_str(&to_json(&tokens).unwrap()).unwrap();
+    let trader = &value[0]["Trader"];
+    assert_eq!(trader["name"]["text"], "Boris");
+    assert_eq!(trader["name"]["comment"]["text"], "main");
+    let item = &trader["categories"][0]["TraderCategory"]["items"][0]["CategoryItem"];
+    assert_eq!(item["class"], "AKM");
+    assert_eq!(item["sell_value"], "500");
+    assert_eq!(item["comment"]["text"], "rifle");
+}
+
+#[test]
+fn text_through_json_formats_like_the_text_itself() {
+    let formatted = format(&parse(CONFIG).unwrap());
+    let json = to_json(&parse(CONFIG).unwrap()).unwrap();
+    assert_eq!(format(&from_json(&json).unwrap()), formatted);
+    assert_eq!(to_json(&from_json(&json).unwrap()).unwrap(), json);
+
+    let exported = common::stdout(common::run(&["--format", "json", "-"], CONFIG));
+    assert_eq!(common::stdout(common::run(&["--from", "json", "-"], exported)), formatted);
+}
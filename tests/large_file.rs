@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+use trader_config_formatter::{format, parse, stats, CurrencyToken};
+
+mod common;
+
+#[test]
+fn large_configs_parse_and_format_idempotently() {
+    let config = common::synthetic_config(40, 20, 50);
+    let tokens = parse(&config).unwrap();
+    let counts = stats(&tokens);
+    assert_eq!(counts.traders.len(), 40);
+    assert_eq!(counts.categories, 40 * 20);
+    assert_eq!(counts.items, 40 * 20 * 50);
+
+    let formatted = format(&tokens);
+    assert_eq!(format(&parse(&formatted).unwrap()), formatted);
+}
+
+#[test]
+fn a_single_line_with_tens_of_thousands_of_values_parses_quickly() {
+    let config = common::long_line_config(50_000);
+    let start = Instant::now();
+    let tokens = parse(&config).unwrap();
+    let elapsed = start.elapsed();
+
+    let currencies = tokens.currency_names().next().unwrap();
+    let CurrencyToken::Currency(line) = &currencies.currencies[0] else { panic!("expected a currency") };
+    assert_eq!(line.values.len(), 50_000);
+    assert_eq!(line.values[49_999], "MoneyRuble49999");
+    // Generous for unoptimized builds, meant to catch parsing that grows faster than the line
+    assert!(elapsed < Duration::from_secs(5), "parsing took {:?}", elapsed);
+}
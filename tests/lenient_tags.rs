@@ -0,0 +1,43 @@
+use trader_config_formatter::{format, parse, parse_with, ParseError, ParseOptions, Token};
+
+const CONFIG: &str = "\
+<currencyname> Rubles
+    <CURRENCY> MoneyRuble100, 100
+<trader> Boris
+    <CATEGORY> Weapons
+        AKM, *, 1000, 500
+<fileend>
+";
+
+fn lenient() -> ParseOptions {
+    ParseOptions { lenient_tags: true, ..ParseOptions::default() }
+}
+
+#[test]
+fn tags_are_case_sensitive_by_default() {
+    assert!(matches!(parse(CONFIG), Err(ParseError::UnknownTag { name, .. }) if name == "currencyname"));
+}
+
+#[test]
+fn lenient_tags_are_recognized_in_any_casing() {
+    let tokens = parse_with(CONFIG, &lenient()).unwrap();
+    assert!(matches!(
+        tokens.as_slice(),
+        [Token::CurrencyName(c), Token::Trader(t), Token::FileEnd(_)] if c.currencies.len() == 1 && t.categories.len() == 1
+    ));
+}
+
+#[test]
+fn lenient_tags_are_written_with_the_canonical_casing() {
+    let formatted = format(&parse_with(CONFIG, &lenient()).unwrap());
+    assert!(formatted.contains("<CurrencyName> Rubles\n"));
+    assert!(formatted.contains("<Currency> MoneyRuble100"));
+    assert!(formatted.contains("<Trader> Boris\n    <Category> Weapons\n"));
+    assert!(formatted.ends_with("<FileEnd>\n"));
+}
+
+#[test]
+fn unknown_tags_are_still_reported() {
+    let err = parse_with("<Trader> Boris\n    <Categori> Weapons\n", &lenient()).unwrap_err();
+    assert!(matches!(err, ParseError::UnknownTag { name, .. } if name == "Categori"));
+}
@@ -0,0 +1,25 @@
+mod common;
+
+const CONFIG: &str = "\
+<OpenFile> TraderConfig.txt
+<Trader> Boris // main
+    <Category> Weapons
+        AKM,*,1000,500
+<Trader> Ivan
+<FileEnd>
+";
+
+#[test]
+fn lines_only_get_a_space_when_a_comment_follows() {
+    let output = common::formatter()
+        .arg("-")
+        .write_stdin(CONFIG)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines.contains(&"<Trader> Boris // main"), "{:?}", lines);
+    assert!(lines.contains(&"<Trader> Ivan"), "{:?}", lines);
+    assert!(lines.contains(&"    <Category> Weapons"), "{:?}", lines);
+}
@@ -0,0 +1,45 @@
+use trader_config_formatter::{parse, CategoryItemToken, Token, TraderCategoryToken};
+
+mod common;
+
+const CONFIG: &str = "\
+<OpenFile> TraderConfig.txt
+<CurrencyName> Rubles
+    <Currency> MoneyRuble100, 100
+<Trader> Boris // main
+    <Category> Weapons
+        AKM, *, 1000, 500
+<FileEnd>
+";
+
+fn format(config: &str) -> String {
+    let output = common::formatter()
+        .arg("-")
+        .write_stdin(config)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn crlf_input_keeps_its_line_endings() {
+    let formatted = format(&CONFIG.replace('\n', "\r\n"));
+    assert!(formatted.split_inclusive('\n').all(|l| l.ends_with("\r\n")), "{:?}", formatted);
+    assert_eq!(formatted.replace("\r\n", "\n"), format(CONFIG));
+
+    // Already formatted, the file comes back byte for byte
+    assert_eq!(format(&formatted), formatted);
+}
+
+#[test]
+fn crlf_line_endings_leave_no_carriage_return_in_values() {
+    let tokens = parse("<Trader> X\r\n    <Category> Y\r\n        A, *, 1, 1\r\n").unwrap();
+    let Token::Trader(trader) = &tokens[0] else { panic!("expected a trader") };
+    let TraderCategoryToken::TraderCategory(category) = &trader.categories[0] else { panic!("expected a category") };
+    let CategoryItemToken::CategoryItem(item) = &category.items[0] else { panic!("expected an item") };
+    assert_eq!(
+        [item.class.as_str(), item.amount.as_str(), item.buy_value.as_str(), item.sell_value.as_str()],
+        ["A", "*", "1", "1"]
+    );
+}
@@ -0,0 +1,45 @@
+use trader_config_formatter::{long_lines, parse, FormatOptions};
+
+mod common;
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500 // a comment long enough to push the formatted line past the default width
+        M4A1, *, 2000, 1000 // short
+<FileEnd>
+";
+
+#[test]
+fn one_over_width_item_is_reported() {
+    let warnings = long_lines(&parse(CONFIG).unwrap(), &FormatOptions::default(), 120);
+    let messages: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(messages, ["Boris > Weapons > AKM: line is 180 characters wide, more than 120"]);
+}
+
+#[test]
+fn width_follows_the_format_options() {
+    let tokens = parse(CONFIG).unwrap();
+    let compact = FormatOptions { compact: true, ..FormatOptions::default() };
+    assert!(long_lines(&tokens, &compact, 120).is_empty());
+    assert_eq!(long_lines(&tokens, &compact, 60).len(), 1);
+}
+
+#[test]
+fn lint_warnings_do_not_fail_the_file() {
+    let dir = common::temp_dir("line_width");
+    let file = dir.join("TraderConfig.txt");
+    std::fs::write(&file, CONFIG).unwrap();
+
+    let output = common::formatter()
+        .arg("--lint")
+        .arg(&file)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("Warning in file").count(), 1, "{}", stderr);
+    assert!(stderr.contains("Boris > Weapons > AKM: line is 180 characters wide, more than 120"), "{}", stderr);
+}
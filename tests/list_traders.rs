@@ -0,0 +1,30 @@
+mod common;
+
+const CONFIG: &str = "tests/fixtures/TraderConfig.txt";
+
+fn list(args: &[&str]) -> String {
+    common::stdout(common::formatter().args(args).arg(CONFIG).output().unwrap())
+}
+
+#[test]
+fn trader_names_are_listed_one_per_line() {
+    assert_eq!(list(&["--list-traders"]), "Boris\nIvan\n");
+}
+
+#[test]
+fn categories_are_listed_under_their_trader() {
+    assert_eq!(list(&["--list-traders", "--list-categories"]), "Boris\n    Weapons\n    Ammo\nIvan\n    Food\n");
+}
+
+#[test]
+fn traders_are_listed_as_json() {
+    let names: Vec<String> = serde_json::from_str(&list(&["--list-traders", "--format", "json"])).unwrap();
+    assert_eq!(names, ["Boris", "Ivan"]);
+
+    let listings: serde_json::Value =
+        serde_json::from_str(&list(&["--list-traders", "--list-categories", "--format", "json"])).unwrap();
+    assert_eq!(listings, serde_json::json!([
+        { "name": "Boris", "categories": ["Weapons", "Ammo"] },
+        { "name": "Ivan", "categories": ["Food"] },
+    ]));
+}
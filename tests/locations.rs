@@ -0,0 +1,25 @@
+mod common;
+
+fn stderr_of(config: &str) -> String {
+    let output = common::formatter()
+        .arg("-")
+        .write_stdin(config)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn errors_report_the_line_and_column_they_start_at() {
+    let cases = [
+        ("<Trader> A\n    <Categ\n", "at 2:5"),
+        ("<Trader> A\n    <Category> B\n        AKM *, 1000, 500\n", "at 3:9"),
+        ("<OpenFile> x\n<Trader> A\n  <Category> B\n        AKM, *, 1000\n", "at 4:9"),
+        ("<CurrencyName> Rubles\n    <Curr\n", "at 2:5"),
+    ];
+    for (config, location) in cases {
+        let stderr = stderr_of(config);
+        assert!(stderr.contains(location), "{:?} should contain {:?}", stderr, location);
+    }
+}
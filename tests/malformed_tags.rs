@@ -0,0 +1,44 @@
+use trader_config_formatter::parse;
+
+// Malformed tags with the kind of error and the location the parser reported before tag names were
+// read as slices of the input, the rewrite must not move them
+const CASES: [(&str, &str, &str); 13] = [
+    ("<Trader> A\n    <Categ\n", "unclosed_tag", "Tag <Categ> at 2:5 isn't closed before the end of the line"),
+    ("<CurrencyName> R\n    <Currency", "unexpected_end", "Tag <Currency> at 2:5 isn't closed before the end of the file"),
+    ("<Trader", "unexpected_end", "Tag <Trader> at 1:1 isn't closed before the end of the file"),
+    ("<Trader\n<FileEnd>\n", "unclosed_tag", "Tag <Trader> at 1:1 isn't closed before the end of the line"),
+    ("<FileEnd", "unexpected_end", "Tag <FileEnd> at 1:1 isn't closed before the end of the file"),
+    ("// c\n<OpenFile\n", "unclosed_tag", "Tag <OpenFile> at 2:1 isn't closed before the end of the line"),
+    ("<CurrencyName> R\n    <Currency\r\n", "unclosed_tag", "Tag <Currency> at 2:5 isn't closed before the end of the line"),
+    ("<Trader> A\n    <Category> W\n        <Bad\n", "unclosed_tag", "Tag <Bad> at 3:9 isn't closed before the end of the line"),
+    (
+        "<Trader> A\n    <Category> W\n        AKM, *, 1, 1 <Bogus\n",
+        "unclosed_tag",
+        "Tag <Bogus> at 3:22 isn't closed before the end of the line",
+    ),
+    ("<Trader> A\n    <Category> W\n        AKM, *, 1, 1\n    <Bogus> x\n", "unknown_tag", "Unknown tag <Bogus> at 4:5"),
+    ("<Bogus> x\n", "unknown_tag", "Unknown tag <Bogus> at 1:1"),
+    ("<>\n", "unknown_tag", "Unknown tag <> at 1:1"),
+    ("<Tra der> x\n", "unknown_tag", "Unknown tag <Tra der> at 1:1"),
+];
+
+#[test]
+fn malformed_tags_are_reported_where_they_were_before() {
+    for (config, kind, message) in CASES {
+        let err = parse(config).unwrap_err();
+        assert_eq!((err.kind(), err.to_string().as_str()), (kind, message), "{:?}", config);
+    }
+}
+
+#[test]
+fn an_unclosed_tag_after_a_byte_order_mark_is_reported_at_the_first_column() {
+    let err = parse("\u{FEFF}<Trader\n").unwrap_err();
+    assert_eq!(err.to_string(), "Tag <Trader> at 1:1 isn't closed before the end of the line");
+    assert_eq!(err.location().unwrap().offset, 3);
+}
+
+#[test]
+fn a_slash_closes_a_tag_like_a_greater_than_sign() {
+    let tokens = parse("<Trader> A\n    <Category/ W\n").unwrap();
+    assert_eq!(tokens.find_trader("A").unwrap().categories.len(), 1);
+}
@@ -0,0 +1,65 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use trader_config_formatter::{parse, parse_iter, write_formatted, write_token, FormatOptions, Token};
+
+mod common;
+
+// Keeps track of the bytes allocated by the whole test binary, which is why this file has a
+// single test
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// Bytes allocated at most while f runs, on top of what was allocated before
+fn peak_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    f();
+    PEAK.load(Ordering::SeqCst) - before
+}
+
+#[test]
+fn streaming_holds_one_block_at_a_time_instead_of_the_whole_file() {
+    let config = common::synthetic_config(100, 20, 50);
+    let opts = FormatOptions::default();
+
+    let collected = peak_during(|| {
+        let tokens = parse(&config).unwrap();
+        write_formatted(&tokens, &mut io::sink(), &opts).unwrap();
+    });
+    let streamed = peak_during(|| {
+        let mut previous = None;
+        for t in parse_iter(&config) {
+            let t = t.unwrap();
+            write_token(&t, previous.as_ref(), &mut io::sink(), &opts).unwrap();
+            if !matches!(t, Token::BlankLine) {
+                previous = Some(t);
+            }
+        }
+    });
+
+    // Only the trader being formatted and the one before it are held, out of 100
+    assert!(streamed * 20 < collected, "streaming peaked at {} bytes, parsing the whole file at {}", streamed, collected);
+}
@@ -0,0 +1,68 @@
+use trader_config_formatter::{format, merge, parse};
+
+const BASE: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+    // ammo below
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<FileEnd>
+";
+
+#[test]
+fn new_items_categories_and_traders_are_appended() {
+    let other = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+        M4A1, *, 2000, 1000
+    <Category> Medical
+        Bandage, *, 20, 10
+<Trader> Ivan
+    <Category> Food
+        Apple, *, 5, 1
+<FileEnd>
+";
+    let mut tokens = parse(BASE).unwrap();
+    let conflicts = merge(&mut tokens, parse(other).unwrap().into_tokens());
+    assert!(conflicts.is_empty());
+
+    let lines: Vec<String> = format(&tokens)
+        .lines()
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|l| !l.is_empty())
+        .collect();
+    assert_eq!(lines, [
+        "<Trader> Boris",
+        "<Category> Weapons",
+        "AKM, *, 1000, 500",
+        "M4A1, *, 2000, 1000",
+        "// ammo below",
+        "<Category> Ammo",
+        "Ammo_762x39, *, 10, 5",
+        "<Category> Medical",
+        "Bandage, *, 20, 10",
+        "<Trader> Ivan",
+        "<Category> Food",
+        "Apple, *, 5, 1",
+        "<FileEnd>",
+    ]);
+}
+
+#[test]
+fn conflicting_prices_are_reported_and_not_overwritten() {
+    let other = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1200, 500
+<FileEnd>
+";
+    let mut tokens = parse(BASE).unwrap();
+    let conflicts = merge(&mut tokens, parse(other).unwrap().into_tokens());
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].trader.as_deref(), Some("Boris"));
+    assert_eq!(conflicts[0].category.as_deref(), Some("Weapons"));
+    assert_eq!(conflicts[0].class.as_deref(), Some("AKM"));
+    assert_eq!(format(&tokens), format(&parse(BASE).unwrap()));
+}
@@ -0,0 +1,119 @@
+use predicates::prelude::*;
+use trader_config_formatter::{
+    ambiguous_prices, normalize_prices, parse, validate, CategoryItemToken, Token, TraderCategoryToken,
+};
+
+mod common;
+
+fn normalized(buy: &str, sell: &str) -> Result<(String, String), Vec<String>> {
+    let config = format!("<Trader> Boris\n    <Category> Weapons\n        AKM, *, {}, {}\n<FileEnd>\n", buy, sell);
+    let mut tokens = parse(&config).unwrap();
+    let violations = normalize_prices(&mut tokens);
+    if !violations.is_empty() {
+        return Err(violations.iter().map(|v| v.to_string()).collect());
+    }
+
+    let Token::Trader(trader) = &tokens[0] else { panic!("expected a trader") };
+    let TraderCategoryToken::TraderCategory(category) = &trader.categories[0] else { panic!("expected a category") };
+    let CategoryItemToken::CategoryItem(item) = &category.items[0] else { panic!("expected an item") };
+    Ok((item.buy_value.clone(), item.sell_value.clone()))
+}
+
+#[test]
+fn sentinel_is_kept() {
+    assert_eq!(normalized("-1", "-1"), Ok(("-1".into(), "-1".into())));
+}
+
+#[test]
+fn sentinel_variants_become_minus_one() {
+    assert_eq!(normalized("-1.0", "- 1"), Ok(("-1".into(), "-1".into())));
+}
+
+#[test]
+fn whole_numbers_are_written_plainly() {
+    assert_eq!(normalized("0100", "100.0"), Ok(("100".into(), "100".into())));
+}
+
+#[test]
+fn zero_becomes_the_sentinel() {
+    for zero in ["0", "00", "0.0", "-0", "- 0.0"] {
+        assert_eq!(normalized(zero, zero), Ok(("-1".into(), "-1".into())), "{}", zero);
+    }
+    assert_eq!(normalized("0%", "5%"), Ok(("0%".into(), "5%".into())));
+}
+
+#[test]
+fn percentages_keep_their_percent_sign() {
+    assert_eq!(normalized("10%", "5%"), Ok(("10%".into(), "5%".into())));
+    assert_eq!(normalized("050 %", "5.0%"), Ok(("50%".into(), "5%".into())));
+    assert_eq!(
+        normalized("-1%", "5%"),
+        Err(vec!["Boris > Weapons > AKM: buy value \"-1%\" is not a whole number, a percentage or -1".to_string()])
+    );
+}
+
+#[test]
+fn normalize_prices_flag_accepts_percentages() {
+    common::formatter()
+        .args(["--normalize-prices", "-"])
+        .write_stdin("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 10%, 5%\n<FileEnd>\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("10%,").and(predicate::str::contains("5%\n")));
+}
+
+#[test]
+fn bad_values_are_reported() {
+    assert_eq!(
+        normalized("12.5", "-2"),
+        Err(vec![
+            "Boris > Weapons > AKM: buy value \"12.5\" is not a whole number, a percentage or -1".to_string(),
+            "Boris > Weapons > AKM: sell value \"-2\" is not a whole number, a percentage or -1".to_string(),
+        ])
+    );
+}
+
+#[test]
+fn thousands_separators_are_dropped() {
+    assert_eq!(normalized("1.000.000", "\"12,500,000\""), Ok(("1000000".into(), "12500000".into())));
+    assert_eq!(normalized("12.500.000", "0.500"), Err(vec![
+        "Boris > Weapons > AKM: sell value \"0.500\" is not a whole number, a percentage or -1".to_string(),
+    ]));
+}
+
+#[test]
+fn values_mixing_separators_are_left_and_warned_about() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, \"1.000,50\", 500\n<FileEnd>\n";
+    let mut tokens = parse(config).unwrap();
+    assert!(normalize_prices(&mut tokens).is_empty());
+    let warnings: Vec<String> = ambiguous_prices(&tokens).iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        warnings,
+        ["Boris > Weapons > AKM: buy value \"1.000,50\" could be a decimal or a number with thousands separators"]
+    );
+    assert!(format!("{}", tokens).contains("\"1.000,50\""));
+}
+
+#[test]
+fn a_single_separator_before_three_digits_is_left_and_warned_about() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1.500, \"1,000\"\n<FileEnd>\n";
+    let mut tokens = parse(config).unwrap();
+    assert!(normalize_prices(&mut tokens).is_empty());
+    let warnings: Vec<String> = ambiguous_prices(&tokens).iter().map(|v| v.to_string()).collect();
+    assert_eq!(warnings, [
+        "Boris > Weapons > AKM: buy value \"1.500\" could be a decimal or a number with thousands separators",
+        "Boris > Weapons > AKM: sell value \"1,000\" could be a decimal or a number with thousands separators",
+    ]);
+    assert!(format!("{}", tokens).contains("1.500"));
+}
+
+#[test]
+fn validate_points_out_thousands_separators() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1.000, \"2,500\"\n<FileEnd>\n";
+    let tokens = parse(config).unwrap();
+    let messages: Vec<String> = validate(&tokens).iter().map(|v| v.to_string()).collect();
+    assert_eq!(messages, [
+        "Boris > Weapons > AKM: buy value \"1.000\" has thousands separators DayZ doesn't read, write it as 1000",
+        "Boris > Weapons > AKM: sell value \"2,500\" has thousands separators DayZ doesn't read, write it as 2500",
+    ]);
+}
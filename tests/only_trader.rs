@@ -0,0 +1,72 @@
+use trader_config_formatter::{format, only_trader, parse, TraderMatch};
+
+mod common;
+
+const CONFIG: &str = "\
+<OpenFile> TraderConfig.txt
+<CurrencyName> Rubles
+    <Currency> MoneyRuble100, 100
+
+// weapons
+<Trader> Boris Weapons
+    <Category> Rifles
+        AKM, *, 1000, 500
+
+// food
+<Trader> Anna Food
+    <Category> Cans
+        BakedBeansCan, *, 50, 25
+<FileEnd>
+";
+
+fn only(args: &[&str]) -> std::process::Output {
+    common::formatter()
+        .args(args)
+        .arg("-")
+        .write_stdin(CONFIG)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn only_the_matching_trader_is_kept() {
+    let tokens = only_trader(parse(CONFIG).unwrap().into_tokens(), "anna food", TraderMatch::Exact, false);
+    let formatted = format(&tokens);
+    assert!(formatted.starts_with("<Trader> Anna Food\n"), "{}", formatted);
+    assert!(formatted.contains("BakedBeansCan") && !formatted.contains("Boris"), "{}", formatted);
+    assert!(only_trader(parse(CONFIG).unwrap().into_tokens(), "anna", TraderMatch::Exact, false).is_empty());
+
+    let tokens = only_trader(parse(CONFIG).unwrap().into_tokens(), "BORIS", TraderMatch::Substring, true);
+    let formatted = format(&tokens);
+    assert!(formatted.starts_with("<OpenFile> TraderConfig.txt\n"), "{}", formatted);
+    assert!(formatted.contains("<Trader> Boris Weapons") && !formatted.contains("Anna"), "{}", formatted);
+    assert!(formatted.ends_with("<FileEnd>\n"), "{}", formatted);
+    assert!(parse(&formatted).is_ok());
+}
+
+#[test]
+fn only_trader_flag_writes_one_of_two_traders() {
+    let output = only(&["--only-trader", "boris", "--trader-match", "substring"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("<Trader> Boris Weapons") && stdout.contains("AKM"), "{}", stdout);
+    assert!(!stdout.contains("Anna") && !stdout.contains("<OpenFile>"), "{}", stdout);
+
+    let output = only(&["--only-trader", "Anna Food", "--with-header"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("<OpenFile>") && stdout.contains("<Trader> Anna Food"), "{}", stdout);
+    assert!(!stdout.contains("Boris"), "{}", stdout);
+
+    let output = only(&["--only-trader", "boris"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No <Trader> matching \"boris\" found"), "{}", stderr);
+}
+
+#[test]
+fn comments_after_a_trader_name_are_not_part_of_it() {
+    let config = "<Trader> Boris /* main */ // note\n    <Category> Weapons\n        AKM, *, 1000, 500\n<Trader> Anna\n";
+    let stdout = common::stdout(common::run(&["--only-trader", "boris", "-"], config));
+    assert!(stdout.starts_with("<Trader> Boris /* main */ // note\n"), "{}", stdout);
+    assert!(!stdout.contains("Anna"), "{}", stdout);
+}
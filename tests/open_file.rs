@@ -0,0 +1,49 @@
+use trader_config_formatter::{format, parse, parse_with, validate, ParseOptions, Token};
+
+#[test]
+fn open_file_argument_survives_formatting() {
+    let config = "<OpenFile> TraderConfig.txt\n<OpenFile>   Traders/My Trader.txt   // main\n<FileEnd>\n";
+    let formatted = format(&parse(config).unwrap());
+    assert!(formatted.starts_with("<OpenFile> TraderConfig.txt\n\n<OpenFile> Traders/My Trader.txt // main\n"));
+
+    let names: Vec<String> = parse(&formatted).unwrap().iter()
+        .filter_map(|t| match t {
+            Token::OpenFile(o) => Some(o.0.text.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["TraderConfig.txt", "Traders/My Trader.txt"]);
+}
+
+#[test]
+fn open_file_must_name_a_txt_file() {
+    let tokens = parse("<OpenFile> TraderConfig.txt\n<OpenFile>\n<OpenFile> Boris.json\n<FileEnd>\n").unwrap();
+    let messages: Vec<String> = validate(&tokens).into_iter().map(|v| v.to_string()).collect();
+    assert_eq!(messages, [
+        "<OpenFile> has no file name",
+        "<OpenFile> file name \"Boris.json\" is not a .txt file",
+    ]);
+}
+
+#[test]
+fn spaced_open_file_headers_round_trip_with_raw_headers() {
+    let config = "<OpenFile>   TraderConfig.txt      // main file\n\
+                  <OpenFile>\tBoris.txt             // weapons\n<FileEnd>\n";
+    let opts = ParseOptions { raw_headers: true, ..ParseOptions::default() };
+    let tokens = parse_with(config, &opts).unwrap();
+    assert_eq!(
+        format(&tokens),
+        "<OpenFile>   TraderConfig.txt      // main file\n\n<OpenFile>\tBoris.txt             // weapons\n\n<FileEnd>\n"
+    );
+
+    // The file name is still read as usual
+    let names: Vec<&str> = tokens.iter()
+        .filter_map(|t| match t {
+            Token::OpenFile(o) => Some(o.0.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["TraderConfig.txt", "Boris.txt"]);
+
+    assert!(format(&parse(config).unwrap()).starts_with("<OpenFile> TraderConfig.txt // main file\n"));
+}
@@ -0,0 +1,33 @@
+use std::fs;
+
+mod common;
+
+const CONFIG: &str = "\
+<OpenFile> TraderConfig.txt
+<Trader> Boris
+    <Category> Weapons
+        AKM,*,1000,500
+<FileEnd>
+";
+
+#[test]
+fn output_file_gets_the_same_bytes_as_stdout() {
+    let dir = common::temp_dir("output");
+    let input = dir.join("TraderConfig.txt");
+    let copy = dir.join("Formatted.txt");
+    fs::write(&input, CONFIG).unwrap();
+
+    let printed = common::formatter().arg(&input).output().unwrap();
+    let written = common::formatter().arg(&input).arg("-o").arg(&copy).output().unwrap();
+    // The input path as output is formatting in place
+    let in_place = common::formatter().arg(&input).arg("-o").arg(&input).output().unwrap();
+    let files = (fs::read(&copy).unwrap(), fs::read(&input).unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(printed.status.success(), "{}", String::from_utf8_lossy(&printed.stderr));
+    assert!(printed.stdout.starts_with(b"<OpenFile> TraderConfig.txt"));
+    assert!(written.status.success() && written.stdout.is_empty());
+    assert!(in_place.status.success() && in_place.stdout.is_empty());
+    assert_eq!(files.0, printed.stdout);
+    assert_eq!(files.1, printed.stdout);
+}
@@ -0,0 +1,19 @@
+mod common;
+
+fn format(config: &str, width: &str) -> String {
+    common::stdout(common::run(&["-", "--width", width], config))
+}
+
+#[test]
+fn values_are_padded_with_spaces_and_never_run_into_the_next_one() {
+    let currencies = format("<CurrencyName> Rubles\n    <Currency> Ruble, 5\n", "10");
+    assert!(!currencies.contains('0'), "{}", currencies);
+    assert!(currencies.contains("Ruble,    5"), "{}", currencies);
+    // Longer than its column, the value still gets one space before the next
+    let currencies = format("<CurrencyName> Rubles\n    <Currency> MoneyRuble100, 100\n", "5");
+    assert!(currencies.contains("MoneyRuble100, 100"), "{}", currencies);
+
+    let items = format("<Trader> Boris\n    <Category> Weapons\n        Very_Long_Class_Name, *, 5, 1234567890 // note\n", "10");
+    assert!(items.contains("Very_Long_Class_Name, *,"), "{}", items);
+    assert!(items.contains("1234567890 // note"), "{}", items);
+}
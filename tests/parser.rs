@@ -0,0 +1,370 @@
+use trader_config_formatter::{
+    parse, parse_all, parse_strict, parse_with_warnings, CategoryItemToken, CurrencyToken, Location, ParseError,
+    ParseOptions, Token, TraderCategoryToken, Trailing,
+};
+
+#[test]
+fn parses_open_file() {
+    let tokens = parse("<OpenFile> TraderConfig.txt // main\n").unwrap();
+    match tokens.as_slice() {
+        [Token::OpenFile(o)] => {
+            assert_eq!(o.0.text, "TraderConfig.txt");
+            assert_eq!(o.0.comment.as_ref().unwrap().text, "main");
+        },
+        other => panic!("unexpected tokens {:?}", other),
+    }
+}
+
+#[test]
+fn parses_file_end() {
+    let tokens = parse("<FileEnd>\n").unwrap();
+    assert!(matches!(tokens.as_slice(), [Token::FileEnd(fe)] if fe.0.text.is_empty()));
+}
+
+#[test]
+fn parses_currency_name_and_currencies() {
+    let tokens = parse("<CurrencyName> Rubles\n    <Currency> MoneyRuble100, 100\n    <Currency> MoneyRuble50, 50\n").unwrap();
+    match tokens.as_slice() {
+        [Token::CurrencyName(c)] => {
+            assert_eq!(c.name.text, "Rubles");
+            let values: Vec<&Vec<String>> = c.currencies.iter()
+                .map(|c| match c {
+                    CurrencyToken::Currency(line) => &line.values,
+                    CurrencyToken::Comment(_) => panic!("unexpected comment"),
+                })
+                .collect();
+            assert_eq!(values, [&vec!["MoneyRuble100", "100"], &vec!["MoneyRuble50", "50"]]);
+        },
+        other => panic!("unexpected tokens {:?}", other),
+    }
+}
+
+#[test]
+fn comments_after_currency_values_do_not_swallow_the_next_currency() {
+    let config = "<CurrencyName> Rubles\n    <Currency> MoneyRuble100, 100\n    <Currency> MoneyRuble50, 50 // note\n    \
+                  // coins below\n    <Currency> MoneyRuble10,10//smallest\r\n<FileEnd>\n";
+    let tokens = parse(config).unwrap();
+    match tokens.as_slice() {
+        [Token::CurrencyName(c), Token::FileEnd(_)] => match c.currencies.as_slice() {
+            [
+                CurrencyToken::Currency(first),
+                CurrencyToken::Currency(second),
+                CurrencyToken::Comment(comment),
+                CurrencyToken::Currency(third),
+            ] => {
+                assert_eq!(first.values, ["MoneyRuble100", "100"]);
+                assert!(first.comment.is_none());
+                assert_eq!(second.values, ["MoneyRuble50", "50"]);
+                assert_eq!(second.comment.as_ref().unwrap().text, "note");
+                assert_eq!(comment.text, "coins below");
+                assert_eq!(third.values, ["MoneyRuble10", "10"]);
+                assert_eq!(third.comment.as_ref().unwrap().text, "smallest");
+            },
+            other => panic!("unexpected currencies {:?}", other),
+        },
+        other => panic!("unexpected tokens {:?}", other),
+    }
+}
+
+#[test]
+fn a_tag_after_currency_values_starts_the_next_currency() {
+    let tokens = parse("<CurrencyName> Rubles\n    <Currency> MoneyRuble100, 100 <Currency> MoneyRuble50, 50\n").unwrap();
+    match tokens.as_slice() {
+        [Token::CurrencyName(c)] => assert_eq!(c.currencies.len(), 2),
+        other => panic!("unexpected tokens {:?}", other),
+    }
+}
+
+#[test]
+fn reports_an_empty_currency() {
+    let err = parse("<CurrencyName> Rubles\n    <Currency>\n    <Currency> MoneyRuble50, 50\n").unwrap_err();
+    assert!(matches!(err, ParseError::EmptyCurrency { location } if location.line == 2 && location.column == 5));
+}
+
+#[test]
+fn parses_trader_and_categories() {
+    let tokens = parse("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n    <Category> Ammo\n<Trader> Ivan\n").unwrap();
+    let traders: Vec<(&str, Vec<&str>)> = tokens.iter()
+        .map(|t| match t {
+            Token::Trader(trader) => {
+                let categories = trader.categories.iter()
+                    .map(|c| match c {
+                        TraderCategoryToken::TraderCategory(c) => c.name.text.as_str(),
+                        TraderCategoryToken::Comment(_) => panic!("unexpected comment"),
+                    })
+                    .collect();
+                (trader.name.text.as_str(), categories)
+            },
+            other => panic!("unexpected token {:?}", other),
+        })
+        .collect();
+    assert_eq!(traders, [("Boris", vec!["Weapons", "Ammo"]), ("Ivan", vec![])]);
+
+    let Token::Trader(boris) = &tokens[0] else { unreachable!() };
+    let TraderCategoryToken::TraderCategory(weapons) = &boris.categories[0] else { unreachable!() };
+    assert!(matches!(&weapons.items[..], [CategoryItemToken::CategoryItem(i)] if i.class == "AKM" && i.sell_value == "500"));
+}
+
+#[test]
+fn reports_unknown_and_unclosed_tags() {
+    assert!(matches!(parse("<Bogus> x\n"), Err(ParseError::UnknownTag { name, .. }) if name == "Bogus"));
+    assert!(matches!(parse("<Trader> A\n    <Categ\n"), Err(ParseError::UnclosedTag { name, .. }) if name == "Categ"));
+    assert!(matches!(parse("<CurrencyName> R\n    <Currency"), Err(ParseError::UnexpectedEnd { name, .. }) if name == "Currency"));
+}
+
+#[test]
+fn reports_the_name_and_location_of_an_unknown_top_level_tag() {
+    let err = parse("<CurrencyName> Rubles\n    <Currency> MoneyRuble1, 1\n<Traders> Boris\n").unwrap_err();
+    assert!(matches!(&err, ParseError::UnknownTag { name, .. } if name == "Traders"), "{:?}", err);
+    assert_eq!(err.to_string(), "Unknown tag <Traders> at 3:1");
+}
+
+#[test]
+fn reports_text_that_is_not_a_tag_at_the_top_level() {
+    let err = parse("garbage here\n<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n").unwrap_err();
+    assert!(matches!(&err, ParseError::UnexpectedText { line, .. } if line == "garbage here"), "{:?}", err);
+    assert_eq!(err.to_string(), "Line at 1:1 isn't a tag or a comment: garbage here");
+
+    // An item before the first trader isn't part of any category
+    let err = parse("<CurrencyName> Rubles\n    <Currency> MoneyRuble1, 1\n  AKM, *, 1000, 500\n<Trader> Boris\n").unwrap_err();
+    assert_eq!(err.to_string(), "Line at 3:3 isn't a tag or a comment: AKM, *, 1000, 500");
+}
+
+#[test]
+fn reports_a_category_outside_a_trader() {
+    let err = parse("<CurrencyName> Rubles\n    <Currency> MoneyRuble1, 1\n<Category> Weapons\n").unwrap_err();
+    assert!(matches!(err, ParseError::MisplacedTag { name: "Category", parent: "Trader", .. }));
+    assert_eq!(err.to_string(), "Tag <Category> at 3:1 must be inside a <Trader>");
+}
+
+#[test]
+fn reports_a_currency_outside_a_currency_name() {
+    let err = parse("<Trader> Boris\n    <Category> Weapons\n    <Currency> MoneyRuble1, 1\n").unwrap_err();
+    assert!(matches!(err, ParseError::MisplacedTag { name: "Currency", parent: "CurrencyName", .. }));
+    assert_eq!(err.to_string(), "Tag <Currency> at 3:5 must be inside a <CurrencyName>");
+}
+
+#[test]
+fn a_stray_line_inside_a_block_is_reported_instead_of_the_tags_after_it() {
+    let err = parse("<Trader> A\n    junk line\n    <Category> X\n        AKM, *, 1000, 500\n").unwrap_err();
+    assert!(matches!(&err, ParseError::UnexpectedText { line, .. } if line == "junk line"), "{:?}", err);
+    assert_eq!(err.to_string(), "Line at 2:5 isn't a tag or a comment: junk line");
+
+    let err = parse("<CurrencyName> Rubles\n    junk line\n    <Currency> MoneyRuble1, 1\n").unwrap_err();
+    assert!(matches!(&err, ParseError::UnexpectedText { line, .. } if line == "junk line"), "{:?}", err);
+    assert_eq!(err.to_string(), "Line at 2:5 isn't a tag or a comment: junk line");
+}
+
+#[test]
+fn a_stray_line_inside_a_block_is_skipped_without_ending_it_when_recovering() {
+    let opts = ParseOptions { recover: true, ..ParseOptions::default() };
+    let config = "<CurrencyName> Rubles\n    junk line\n    <Currency> MoneyRuble1, 1\n<Trader> A\n    junk line\n    <Category> X\n        AKM, *, 1000, 500\n";
+    let (document, skipped) = parse_with_warnings(config, &opts).unwrap();
+    let currency = document.currency_names().next().unwrap();
+    assert!(matches!(&currency.currencies[..], [CurrencyToken::Comment(_), CurrencyToken::Currency(_)]));
+    let trader = document.find_trader("A").unwrap();
+    assert!(matches!(&trader.categories[..], [TraderCategoryToken::Comment(_), TraderCategoryToken::TraderCategory(_)]));
+    let locations: Vec<String> = skipped.iter().map(|e| e.location().unwrap().to_string()).collect();
+    assert_eq!(locations, ["2:5", "5:5"]);
+}
+
+#[test]
+fn strict_parsing_requires_file_end() {
+    let with_end = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n<FileEnd>\n";
+    assert!(parse_strict(with_end).is_ok());
+
+    let without_end = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n";
+    assert!(matches!(parse_strict(without_end), Err(ParseError::MissingFileEnd)));
+    assert!(parse(without_end).is_ok());
+}
+
+#[test]
+fn content_after_file_end_is_kept_unless_strict() {
+    let contents = "<Trader> Boris\n<FileEnd>\n\n<Trader> Ivan\nold notes  \n";
+    let tokens = parse(contents).unwrap();
+    match tokens.last() {
+        Some(Token::Trailing(t)) => assert_eq!(t.text, "<Trader> Ivan\nold notes"),
+        other => panic!("unexpected token {:?}", other),
+    }
+    assert_eq!(
+        trader_config_formatter::format(&tokens),
+        "<Trader> Boris\n\n<FileEnd>\n\n<Trader> Ivan\nold notes\n"
+    );
+
+    // Tools can add notes after <FileEnd> themselves
+    let mut tokens = tokens.into_tokens();
+    tokens.pop();
+    tokens.push(Token::Trailing(Trailing { text: "new notes".to_string(), location: Location::default() }));
+    assert!(trader_config_formatter::format(&tokens).ends_with("<FileEnd>\n\nnew notes\n"));
+
+    let err = parse_strict(contents).unwrap_err();
+    assert!(matches!(err, ParseError::ContentAfterFileEnd { location } if location.line == 4 && location.column == 1));
+    assert!(parse_strict("<Trader> Boris\n<FileEnd>\n  \n").is_ok());
+}
+
+#[test]
+fn empty_and_whitespace_only_files_have_no_tokens() {
+    for contents in ["", "  \n\t\r\n  \n"] {
+        assert!(parse(contents).unwrap().is_empty());
+        assert!(matches!(parse_strict(contents), Err(ParseError::EmptyFile)));
+    }
+}
+
+#[test]
+fn comment_only_files_are_not_empty() {
+    let contents = "// nothing here yet\n/* still\n   nothing */\n";
+    assert_eq!(parse(contents).unwrap().len(), 2);
+    assert!(matches!(parse_strict(contents), Err(ParseError::MissingFileEnd)));
+}
+
+#[test]
+fn empty_value_is_reported_with_its_position() {
+    let err = parse("<Trader> Boris\n    <Category> Weapons\n        ClassA,,100,200\n").unwrap_err();
+    assert!(matches!(err, ParseError::EmptyCsvValue { position: 2, .. }));
+    assert_eq!(err.to_string(), "Value 2 of the line at 3:9 is empty, probably an extra comma: ClassA,,100,200");
+
+    let err = parse("<CurrencyName> Rubles\n    <Currency> , MoneyRuble1, 1\n").unwrap_err();
+    assert!(matches!(err, ParseError::EmptyCsvValue { position: 1, .. }));
+}
+
+#[test]
+fn delimiter_after_the_last_value_is_ignored() {
+    let tokens = parse("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500,\n").unwrap();
+    let values: Vec<&str> = trader_config_formatter::items(&tokens)
+        .flat_map(|(_, _, i)| [i.class.as_str(), i.amount.as_str(), i.buy_value.as_str(), i.sell_value.as_str()])
+        .collect();
+    assert_eq!(values, ["AKM", "*", "1000", "500"]);
+}
+
+#[test]
+fn missing_comma_reports_value_count_and_line() {
+    let err = parse("<Trader> Boris\n    <Category> Weapons\n        AKM *, 1000, 500 // rifle\n").unwrap_err();
+    assert!(matches!(err, ParseError::MissingCsvValues { found: 3, .. }));
+    assert_eq!(
+        err.to_string(),
+        "Category item at 3:9 has 3 values instead of 4 or 5 (class, amount, buy value, sell value, stock), \
+         probably a missing comma: AKM *, 1000, 500 // rifle"
+    );
+}
+
+#[test]
+fn parse_all_reports_independent_errors() {
+    let config = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM *, 1000, 500
+        M4A1, *, 2000, 1000
+<Trader> Ivan
+    <Categ
+        Apple, *, 5, 1
+<Trader> Sasha
+    <Category> Food
+        Pear, *, 5, 1
+<FileEnd>
+";
+    let errors = parse_all(config).unwrap_err();
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(matches!(errors[0], ParseError::MissingCsvValues { found: 3, .. }));
+    assert!(matches!(&errors[1], ParseError::UnclosedTag { name, .. } if name == "Categ"));
+
+    let valid = parse_all("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n<FileEnd>\n").unwrap();
+    assert_eq!(valid.traders().count(), 1);
+}
+
+#[test]
+fn parse_all_stops_skipping_at_an_unclosed_top_level_tag() {
+    let config = "\
+<Trader> A
+    <Category> Weapons
+        AKM *, 1000, 500
+        M4A1, *, 2000, 1000
+<Trader C
+    <Category> Food
+<Trader> D
+    <Categ
+<FileEnd>
+";
+    let errors = parse_all(config).unwrap_err();
+    let lines: Vec<usize> = errors.iter().map(|e| e.location().unwrap().line).collect();
+    assert_eq!(lines, [3, 5, 8], "{:?}", errors);
+    assert!(matches!(&errors[1], ParseError::UnclosedTag { name, .. } if name == "Trader C"));
+}
+
+#[test]
+fn errors_report_the_byte_offset_of_the_bad_tag() {
+    let config = "// Händler für Waffen\n<Trader> Boris\n    <Bogus> x\n";
+    let location = parse(config).unwrap_err().location().unwrap();
+    assert_eq!(location.offset, config.find("<Bogus>").unwrap());
+    assert_eq!((location.line, location.column), (3, 5));
+
+    // The byte order mark is part of the file
+    let with_bom = format!("\u{FEFF}{}", config);
+    let location = parse(&with_bom).unwrap_err().location().unwrap();
+    assert_eq!(location.offset, with_bom.find("<Bogus>").unwrap());
+}
+
+#[test]
+fn a_stray_currency_name_inside_a_currency_block_ends_it_with_a_warning() {
+    let config = "\
+<CurrencyName> Rubles
+    <Currency> MoneyRuble100, 100
+    <CurrencyName> Dollars
+    <Currency> MoneyDollar1, 1
+<CurrencyName> Euros
+    <Currency> MoneyEuro1, 1
+    <Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+<FileEnd>
+";
+    let (document, warnings) = parse_with_warnings(config, &ParseOptions::default()).unwrap();
+    let names: Vec<&str> = document.currency_names().map(|c| c.name.text.as_str()).collect();
+    assert_eq!(names, ["Rubles", "Dollars", "Euros"]);
+    assert!(document.currency_names().all(|c| c.currencies.len() == 1));
+    assert_eq!(document.items().count(), 1);
+
+    let names: Vec<&str> = warnings.iter()
+        .map(|w| match w {
+            ParseError::NestedBlock { name, .. } => name.as_str(),
+            other => panic!("unexpected warning {:?}", other),
+        })
+        .collect();
+    assert_eq!(names, ["CurrencyName", "Trader"]);
+
+    // Euros starts at the same column as Rubles, only the indented tags are warned about
+    let warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        [
+            "Tag <CurrencyName> at 3:5 is indented inside the <CurrencyName> at 1:1, it ends that block and starts another",
+            "Tag <Trader> at 7:5 is indented inside the <CurrencyName> at 5:1, it ends that block and starts another",
+        ]
+    );
+}
+
+#[test]
+fn a_line_of_only_delimiters_inside_a_category_is_an_empty_value() {
+    let err = parse("<Trader> Boris\n    <Category> Weapons\n        ,\n        AKM, *, 1000, 500\n").unwrap_err();
+    assert!(matches!(err, ParseError::EmptyCsvValue { position: 1, .. }), "{:?}", err);
+    assert_eq!(err.to_string(), "Value 1 of the line at 3:9 is empty, probably an extra comma: ,");
+}
+
+#[test]
+fn carriage_returns_end_the_line_of_an_empty_tag() {
+    // Lines end at a carriage return on its own too, so it isn't skipped as a space after a tag
+    for line_ending in ["\r\n", "\r"] {
+        let config = ["<OpenFile>", "<Trader> X", "    <Category> Y", "        A, *, 1, 1", ""].join(line_ending);
+        let tokens = parse(&config).unwrap();
+        match tokens.as_slice() {
+            [Token::OpenFile(o), Token::Trader(t)] => {
+                assert_eq!(o.0.text, "");
+                assert_eq!(t.name.text, "X");
+            },
+            other => panic!("unexpected tokens {:?}", other),
+        }
+        let values: Vec<&str> = trader_config_formatter::items(&tokens)
+            .flat_map(|(_, _, i)| [i.class.as_str(), i.amount.as_str(), i.buy_value.as_str(), i.sell_value.as_str()])
+            .collect();
+        assert_eq!(values, ["A", "*", "1", "1"]);
+    }
+}
@@ -0,0 +1,36 @@
+use trader_config_formatter::{format, parse, CategoryItemToken, Token, TraderCategoryToken};
+
+fn first_item_class(tokens: &[Token]) -> String {
+    let Some(Token::Trader(trader)) = tokens.first() else { panic!("expected a trader") };
+    let Some(TraderCategoryToken::TraderCategory(category)) = trader.categories.first() else { panic!("expected a category") };
+    let Some(CategoryItemToken::CategoryItem(item)) = category.items.first() else { panic!("expected an item") };
+    item.class.clone()
+}
+
+#[test]
+fn quoted_value_with_a_comma_survives_a_round_trip() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        \"AKM, modded\", *, 1000, 500 // gun\n<FileEnd>\n";
+    let tokens = parse(config).unwrap();
+    assert_eq!(first_item_class(&tokens), "AKM, modded");
+
+    let formatted = format(&tokens);
+    assert!(formatted.contains("\"AKM, modded\","));
+    let reparsed = parse(&formatted).unwrap();
+    assert_eq!(first_item_class(&reparsed), "AKM, modded");
+    assert_eq!(format(&reparsed), formatted);
+}
+
+#[test]
+fn quotes_and_slashes_inside_quoted_values_are_kept() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        \"Say \"\"hi\"\" // not a comment\", *, 1, 2\n<FileEnd>\n";
+    let tokens = parse(config).unwrap();
+    assert_eq!(first_item_class(&tokens), "Say \"hi\" // not a comment");
+    assert_eq!(first_item_class(&parse(&format(&tokens)).unwrap()), "Say \"hi\" // not a comment");
+}
+
+#[test]
+fn currency_values_are_requoted() {
+    let config = "<CurrencyName> Rubles\n    <Currency> \"Money, Ruble\", 100\n<FileEnd>\n";
+    let formatted = format(&parse(config).unwrap());
+    assert!(formatted.contains("<Currency> \"Money, Ruble\","));
+}
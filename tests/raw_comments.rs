@@ -0,0 +1,38 @@
+use trader_config_formatter::{format, parse, parse_with, ParseOptions, Token};
+
+mod common;
+
+const CONFIG: &str = "\
+//   ===== WEAPONS =====
+#\tby Boris
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500 //    best seller
+<FileEnd>
+";
+
+fn raw() -> ParseOptions {
+    ParseOptions { raw_comments: true, ..ParseOptions::default() }
+}
+
+#[test]
+fn comments_are_trimmed_by_default() {
+    let tokens = parse(CONFIG).unwrap();
+    assert!(matches!(&tokens[0], Token::Comment(c) if c.text == "===== WEAPONS ====="));
+    assert!(format(&tokens).starts_with("// ===== WEAPONS =====\n# by Boris\n"));
+}
+
+#[test]
+fn raw_comments_keep_their_spacing() {
+    let tokens = parse_with(CONFIG, &raw()).unwrap();
+    assert!(matches!(&tokens[0], Token::Comment(c) if c.text == "   ===== WEAPONS ====="));
+    let formatted = format(&tokens);
+    assert!(formatted.starts_with("//   ===== WEAPONS =====\n#\tby Boris\n"), "{}", formatted);
+    assert!(formatted.contains(" //    best seller\n"), "{}", formatted);
+    assert_eq!(format(&parse_with(&formatted, &raw()).unwrap()), formatted);
+}
+
+#[test]
+fn raw_comments_flag_keeps_the_spacing() {
+    assert!(common::stdout(common::run(&["--raw-comments", "-"], CONFIG)).starts_with("//   ===== WEAPONS =====\n"));
+}
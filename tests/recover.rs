@@ -0,0 +1,67 @@
+use trader_config_formatter::{format, items, parse, parse_with_warnings, ParseError, ParseOptions};
+
+mod common;
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+        M4A1 *, 1000 // missing commas
+        SVD, *, 2000, 1000
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<FileEnd>
+";
+
+fn recover() -> ParseOptions {
+    ParseOptions { recover: true, ..ParseOptions::default() }
+}
+
+#[test]
+fn items_after_a_broken_line_are_still_parsed() {
+    assert!(parse(CONFIG).is_err());
+
+    let (parsed, skipped) = parse_with_warnings(CONFIG, &recover()).unwrap();
+    let classes: Vec<&str> = items(&parsed).map(|(_, _, i)| i.class.as_str()).collect();
+    assert_eq!(classes, ["AKM", "SVD", "Ammo_762x39"]);
+    match skipped.as_slice() {
+        [ParseError::MissingCsvValues { found: 2, location, .. }] => assert_eq!(location.line, 4),
+        other => panic!("unexpected errors {:?}", other),
+    }
+}
+
+#[test]
+fn skipped_lines_are_kept_as_marked_comments() {
+    let (parsed, _) = parse_with_warnings(CONFIG, &recover()).unwrap();
+    let formatted = format(&parsed);
+    let skipped: Vec<&str> = formatted.lines().filter(|l| l.contains("SKIPPED")).collect();
+    assert_eq!(skipped, [format!("{}// SKIPPED: M4A1 *, 1000 // missing commas", " ".repeat(16))]);
+    assert!(parse(&formatted).is_ok());
+}
+
+#[test]
+fn errors_outside_of_items_still_fail() {
+    let config = "<Trader> Boris\n<Categry> Weapons\n";
+    assert!(matches!(parse_with_warnings(config, &recover()), Err(ParseError::UnknownTag { .. })));
+}
+
+#[test]
+fn stray_top_level_lines_are_kept_as_marked_comments() {
+    let config = "garbage here\n<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n";
+    let (parsed, skipped) = parse_with_warnings(config, &recover()).unwrap();
+    assert!(format(&parsed).starts_with("// SKIPPED: garbage here\n"));
+    match skipped.as_slice() {
+        [ParseError::UnexpectedText { location, .. }] => assert_eq!((location.line, location.column), (1, 1)),
+        other => panic!("unexpected skipped lines {:?}", other),
+    }
+}
+
+#[test]
+fn recover_reports_skipped_lines_as_warnings() {
+    let output = common::run(&["--recover", "-"], CONFIG);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("// SKIPPED: M4A1"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Warning in file -: skipped a line that failed to parse: Category item at 4:9"), "{}", stderr);
+}
@@ -0,0 +1,15 @@
+use trader_config_formatter::{parse, validate};
+
+#[test]
+fn each_extra_listing_of_a_class_is_reported_once() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n        M4A1, *, 2000, 1000\n\
+                  \x20       AKM, *, 900, 400\n        AKM, *, 800, 300\n";
+    let messages: Vec<String> = validate(&parse(config).unwrap()).iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Boris > Weapons > AKM: class is listed more than once in the category, first at 3:9 and again at 5:9",
+            "Boris > Weapons > AKM: class is listed more than once in the category, first at 3:9 and again at 6:9",
+        ],
+    );
+}
@@ -0,0 +1,199 @@
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use trader_config_formatter::{
+    format_with, parse_with, to_json, CSVLine, CategoryItem, CategoryItemToken, Comment, CommentStyle, CurrencyName,
+    CurrencyToken, FileEnd, FormatOptions, Line, Location, OpenFile, ParseOptions, Token, Trader, TraderCategory,
+    TraderCategoryToken,
+};
+
+fn name() -> impl Strategy<Value = String> {
+    "[A-Za-z][A-Za-z0-9_]{0,10}"
+}
+
+fn comment_text() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9 =.,!<>-]{0,16}".prop_map(|t| t.trim().to_string())
+}
+
+// ; and # only start a comment at the beginning of a line, trailing comments use // or /* */
+fn comment(own_line: bool) -> impl Strategy<Value = Comment> {
+    let styles = if own_line {
+        vec![CommentStyle::Line, CommentStyle::Block, CommentStyle::Semicolon, CommentStyle::Hash]
+    } else {
+        vec![CommentStyle::Line, CommentStyle::Block]
+    };
+    (comment_text(), proptest::sample::select(styles)).prop_map(|(text, style)| {
+        // Block comments keep their text as written, spaces around it included
+        let text = if style == CommentStyle::Block { format!(" {} ", text) } else { text };
+        Comment { text, style, raw: false, location: Location::default() }
+    })
+}
+
+fn line() -> impl Strategy<Value = Line> {
+    (name(), option::of(comment(false)))
+        .prop_map(|(text, comment)| Line { text, block_comments: Vec::new(), comment, raw: None })
+}
+
+fn price() -> impl Strategy<Value = String> {
+    prop_oneof![Just("-1".to_string()), (0u32..100_000).prop_map(|p| p.to_string())]
+}
+
+fn item() -> impl Strategy<Value = CategoryItem> {
+    let amount = prop_oneof![
+        Just("*".to_string()),
+        Just("M".to_string()),
+        Just("VNK".to_string()),
+        (1u32..500).prop_map(|a| a.to_string()),
+    ];
+    (name(), amount, price(), price(), option::of((0u32..100).prop_map(|s| s.to_string())), option::of(comment(false)))
+        .prop_map(|(class, amount, buy_value, sell_value, stock, comment)| CategoryItem {
+            class,
+            amount,
+            buy_value,
+            sell_value,
+            stock,
+            comment,
+            location: Location::default(),
+        })
+}
+
+fn category() -> impl Strategy<Value = TraderCategory> {
+    let items = vec(
+        prop_oneof![
+            3 => item().prop_map(CategoryItemToken::CategoryItem),
+            1 => comment(true).prop_map(CategoryItemToken::Comment),
+        ],
+        0..6,
+    );
+    (line(), items).prop_map(|(name, items)| TraderCategory { name, items })
+}
+
+// Comments after a category's items belong to the category, so a trader's own comments come first
+fn trader() -> impl Strategy<Value = Trader> {
+    (line(), vec(comment(true), 0..2), vec(category(), 0..4)).prop_map(|(name, comments, categories)| Trader {
+        name,
+        categories: comments.into_iter()
+            .map(TraderCategoryToken::Comment)
+            .chain(categories.into_iter().map(TraderCategoryToken::TraderCategory))
+            .collect(),
+    })
+}
+
+fn currency_name() -> impl Strategy<Value = CurrencyName> {
+    let currency = (name(), 1u32..10_000, option::of(comment(false))).prop_map(|(class, value, comment)| {
+        CSVLine { values: vec![class, value.to_string()], comment, location: Location::default() }
+    });
+    let currencies = vec(
+        prop_oneof![
+            3 => currency.prop_map(CurrencyToken::Currency),
+            1 => comment(true).prop_map(CurrencyToken::Comment),
+        ],
+        0..4,
+    );
+    (line(), currencies).prop_map(|(name, currencies)| CurrencyName { name, currencies })
+}
+
+// Top-level comments only come before the first block, after it they're read as part of the block
+fn config() -> impl Strategy<Value = Vec<Token>> {
+    (
+        vec(comment(true), 0..3),
+        option::of(line()),
+        vec(currency_name(), 0..3),
+        vec(trader(), 0..4),
+        option::of(option::of(comment(false))),
+    )
+        .prop_map(|(comments, open_file, currencies, traders, file_end)| {
+            comments.into_iter()
+                .map(Token::Comment)
+                .chain(open_file.map(|l| Token::OpenFile(OpenFile(l))))
+                .chain(currencies.into_iter().map(Token::CurrencyName))
+                .chain(traders.into_iter().map(Token::Trader))
+                .chain(file_end.map(|comment| {
+                    Token::FileEnd(FileEnd(Line { text: String::new(), block_comments: Vec::new(), comment, raw: None }))
+                }))
+                .collect()
+        })
+}
+
+fn assert_round_trip(tokens: &[Token], format: &FormatOptions) -> Result<(), TestCaseError> {
+    let formatted = format_with(tokens, format);
+    let parse = ParseOptions { delimiter: format.delimiter, ..ParseOptions::default() };
+    let mut parsed = parse_with(&formatted, &parse)
+        .map_err(|err| TestCaseError::fail(format!("{}\n{}", err, formatted)))?;
+    // Blank lines between blocks are kept as tokens for --preserve-blank-lines
+    parsed.retain(|t| !matches!(t, Token::BlankLine));
+    prop_assert_eq!(to_json(&parsed).unwrap(), to_json(tokens).unwrap(), "{}", formatted);
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn formatted_tokens_parse_back_to_the_same_tokens(tokens in config()) {
+        assert_round_trip(&tokens, &FormatOptions::default())?;
+    }
+
+    #[test]
+    fn compact_and_tab_separated_tokens_parse_back_to_the_same_tokens(tokens in config()) {
+        assert_round_trip(&tokens, &FormatOptions { compact: true, ..FormatOptions::default() })?;
+        assert_round_trip(&tokens, &FormatOptions { delimiter: '\t', ..FormatOptions::default() })?;
+    }
+}
+
+fn header_comments(tokens: &[Token]) -> Vec<(String, Option<String>)> {
+    let line = |l: &Line| {
+        let comments: Vec<String> = l.comments().map(|c| c.to_string()).collect();
+        (l.text.clone(), Some(comments.join(" ")).filter(|c| !c.is_empty()))
+    };
+    let mut headers = Vec::new();
+    for token in tokens {
+        match token {
+            Token::OpenFile(o) => headers.push(line(&o.0)),
+            Token::FileEnd(fe) => headers.push(line(&fe.0)),
+            Token::Trader(t) => {
+                headers.push(line(&t.name));
+                for c in &t.categories {
+                    if let TraderCategoryToken::TraderCategory(c) = c {
+                        headers.push(line(&c.name));
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+    headers
+}
+
+#[test]
+fn comments_on_tag_lines_stay_on_the_tag_line() {
+    let config = "\
+<OpenFile> TraderConfig.txt // see http://example.com/a?b=1
+<Trader> Boris // main trader <Boris> / 50% off, \"quoted\"
+    <Category> weapons // WIP
+        M4A1, *, 2000, 1000
+    <Category> Boris/Ivan /* block */
+    <Category> ammo /* a */ // b
+    <Category> empty //
+<FileEnd> // end <x>
+";
+    let tokens = parse_with(config, &ParseOptions::default()).unwrap();
+    let expected = [
+        ("TraderConfig.txt", Some("// see http://example.com/a?b=1")),
+        ("Boris", Some("// main trader <Boris> / 50% off, \"quoted\"")),
+        ("weapons", Some("// WIP")),
+        ("Boris/Ivan", Some("/* block */")),
+        ("ammo", Some("/* a */ // b")),
+        ("empty", Some("//")),
+        ("", Some("// end <x>")),
+    ];
+    let expected: Vec<(String, Option<String>)> =
+        expected.iter().map(|(t, c)| (t.to_string(), c.map(String::from))).collect();
+    assert_eq!(header_comments(&tokens), expected);
+
+    let formatted = format_with(&tokens, &FormatOptions::default());
+    assert!(formatted.contains("<Trader> Boris // main trader <Boris> / 50% off"), "{}", formatted);
+    assert!(formatted.contains("<Category> weapons // WIP\n"), "{}", formatted);
+    assert!(formatted.contains("<Category> ammo /* a */ // b\n"), "{}", formatted);
+    assert!(formatted.contains("<Category> empty //\n"), "{}", formatted);
+    let parsed = parse_with(&formatted, &ParseOptions::default()).unwrap();
+    assert_eq!(header_comments(&parsed), expected);
+}
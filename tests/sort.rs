@@ -0,0 +1,84 @@
+use trader_config_formatter::{format, parse, sort_categories, sort_traders};
+
+const CONFIG: &str = "\
+<CurrencyName> Rubles
+    <Currency> MoneyRuble1, 1
+
+// Weapons
+<Trader> Zed
+    <Category> Rifles
+        M4A1, *, 100, 50
+    // Ammunition first
+    <Category> Ammo
+        Ammo_556x45, *, 10, 5
+    <Category> Pistols
+        FNX45, *, 80, 40
+    // Trailing comment
+
+// Food
+<Trader> Anna
+    <Category> Food
+        Apple, *, 5, 2
+// End of traders
+<FileEnd>
+";
+
+fn formatted(sort: fn(&mut Vec<trader_config_formatter::Token>)) -> String {
+    let mut tokens = parse(CONFIG).unwrap();
+    sort(&mut tokens);
+    format(&tokens)
+}
+
+fn names(formatted: &str, tag: &str) -> Vec<String> {
+    formatted
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix(tag))
+        .map(|l| l.trim().to_string())
+        .collect()
+}
+
+#[test]
+fn sorting_categories_orders_them_by_name() {
+    let out = formatted(|t| sort_categories(t));
+    assert_eq!(names(&out, "<Category>"), ["Ammo", "Pistols", "Rifles", "Food"]);
+    assert_eq!(names(&out, "<Trader>"), ["Zed", "Anna"]);
+}
+
+#[test]
+fn sorting_categories_keeps_comments_with_the_next_category() {
+    let out = formatted(|t| sort_categories(t));
+    let lines: Vec<&str> = out.lines().map(|l| l.trim()).collect();
+    let ammo = lines.iter().position(|l| *l == "<Category> Ammo").unwrap();
+    assert_eq!(lines[ammo - 1], "// Ammunition first");
+    let trailing = lines.iter().position(|l| *l == "// Trailing comment").unwrap();
+    assert!(lines[trailing - 1].starts_with("M4A1,"));
+}
+
+#[test]
+fn sorting_traders_orders_them_by_name() {
+    let out = formatted(sort_traders);
+    assert_eq!(names(&out, "<Trader>"), ["Anna", "Zed"]);
+    assert_eq!(names(&out, "<Category>"), ["Food", "Rifles", "Ammo", "Pistols"]);
+}
+
+#[test]
+fn sorting_traders_keeps_comments_with_the_next_trader() {
+    let out = formatted(sort_traders);
+    let lines: Vec<&str> = out.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    assert!(lines.starts_with(&["<CurrencyName> Rubles"]));
+    let anna = lines.iter().position(|l| *l == "<Trader> Anna").unwrap();
+    assert_eq!(lines[anna - 1], "// Food");
+    let zed = lines.iter().position(|l| *l == "<Trader> Zed").unwrap();
+    assert_eq!(lines[zed - 1], "// Weapons");
+    assert_eq!(&lines[lines.len() - 2..], ["// End of traders", "<FileEnd>"]);
+}
+
+#[test]
+fn sorting_is_independent_and_composes() {
+    let mut tokens = parse(CONFIG).unwrap();
+    sort_categories(&mut tokens);
+    sort_traders(&mut tokens);
+    let out = format(&tokens);
+    assert_eq!(names(&out, "<Trader>"), ["Anna", "Zed"]);
+    assert_eq!(names(&out, "<Category>"), ["Food", "Ammo", "Pistols", "Rifles"]);
+}
@@ -0,0 +1,50 @@
+use trader_config_formatter::{format, parse, split, Token};
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+
+fn trader_names(tokens: &[Token]) -> Vec<&str> {
+    tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Trader(trader) => Some(trader.name.text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn every_trader_gets_its_own_file() {
+    let files = split(parse(CONFIG).unwrap().into_tokens());
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Boris.txt", "Ivan.txt"]);
+
+    for ((_, tokens), trader) in files.iter().zip(["Boris", "Ivan"]) {
+        assert_eq!(trader_names(tokens), [trader]);
+        assert!(matches!(tokens.first(), Some(Token::Comment(_))));
+        assert!(tokens.iter().any(|t| matches!(t, Token::OpenFile(_))));
+        assert!(tokens.iter().any(|t| matches!(t, Token::CurrencyName(_))));
+        assert!(matches!(tokens.last(), Some(Token::FileEnd(_))));
+    }
+
+    let ivan = format(&files[1].1);
+    assert!(ivan.contains("Pear,"));
+    assert!(!ivan.contains("AKM,"));
+}
+
+#[test]
+fn trader_names_are_sanitized_into_file_names() {
+    let config = "\
+<Trader> Black Market: Weapons/Ammo
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<Trader> black market: weapons/ammo
+    <Category> Ammo
+        Ammo_556x45, *, 10, 5
+<Trader> ..
+    <Category> Ammo
+        Ammo_9x19, *, 10, 5
+";
+    let files = split(parse(config).unwrap().into_tokens());
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Black_Market__Weapons_Ammo.txt", "black_market__weapons_ammo_2.txt", "Trader.txt"]);
+}
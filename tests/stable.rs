@@ -0,0 +1,27 @@
+use std::fs;
+
+use trader_config_formatter::{parse, Token};
+
+// Running the tests at all means the crate built, this keeps nightly features from coming back
+#[test]
+fn no_source_file_enables_nightly_features() {
+    for entry in fs::read_dir("src").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "rs") {
+            let source = fs::read_to_string(&path).unwrap();
+            assert!(!source.contains("#![feature"), "{} enables a nightly feature", path.display());
+        }
+    }
+}
+
+#[test]
+fn tag_values_start_right_after_the_closing_bracket() {
+    let tokens = parse("<OpenFile>TraderConfig.txt\n<Trader>Boris\n<FileEnd>\n").unwrap();
+    match tokens.as_slice() {
+        [Token::OpenFile(o), Token::Trader(t), Token::FileEnd(_)] => {
+            assert_eq!(o.0.text, "TraderConfig.txt");
+            assert_eq!(t.name.text, "Boris");
+        },
+        other => panic!("unexpected tokens {:?}", other),
+    }
+}
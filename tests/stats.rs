@@ -0,0 +1,48 @@
+use trader_config_formatter::{parse, stats, Stats, TraderStats};
+use unicode_width::UnicodeWidthStr;
+
+const CONFIG: &str = "\
+// Two traders
+<CurrencyName> Rubles
+    <Currency> MoneyRuble100, 100
+    <Currency> MoneyRuble50, 50
+<Trader> Boris
+    <Category> Weapons // guns
+        AKM, *, 1000, 500
+        M4A1, *, 2000, 1000
+    <Category> Ammo
+        Ammo_762x39, *, 10, 5
+<Trader> Ivan
+    <Category> Food
+        // canned
+        BakedBeansCan, *, 50, 25
+<FileEnd>
+";
+
+#[test]
+fn counts_every_trader_with_its_categories_and_items() {
+    let trader = |name: &str, categories, items| TraderStats { name: name.to_string(), categories, items };
+    assert_eq!(
+        stats(&parse(CONFIG).unwrap()),
+        Stats {
+            traders: vec![trader("Boris", 2, 3), trader("Ivan", 1, 1)],
+            categories: 3,
+            items: 4,
+            currencies: 2,
+            comments: 3,
+        }
+    );
+}
+
+#[test]
+fn wide_trader_names_keep_the_counts_lined_up() {
+    let config = "<Trader> \u{30dc}\u{30ea}\u{30b9}\n    <Category> Weapons\n        AKM, *, 1000, 500\n<Trader> Ivan\n<FileEnd>\n";
+    let printed = stats(&parse(config).unwrap()).to_string();
+    let columns: Vec<usize> = printed
+        .lines()
+        .filter(|l| l.contains("categories"))
+        .map(|l| l[..l.find("categories").unwrap()].width())
+        .collect();
+    assert_eq!(columns.len(), 2, "{}", printed);
+    assert_eq!(columns[0], columns[1], "{}", printed);
+}
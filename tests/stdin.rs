@@ -0,0 +1,43 @@
+use std::fs;
+use std::io::Cursor;
+
+use trader_config_formatter::{format, parse, read_from, ParseError};
+
+mod common;
+
+const CONFIG: &str = "\
+<OpenFile> TraderConfig.txt
+<Trader> Boris
+    <Category> Weapons
+        AKM,*,1000,500
+<FileEnd>
+";
+
+#[test]
+fn config_piped_through_stdin_is_printed_like_the_file() {
+    let piped = common::formatter().arg("-").write_stdin(CONFIG).output().unwrap();
+    assert!(piped.status.success());
+
+    let dir = common::temp_dir("stdin");
+    let path = dir.join("TraderConfig.txt");
+    fs::write(&path, CONFIG).unwrap();
+    let from_file = common::formatter().arg(&path).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(piped.stdout, from_file.stdout);
+}
+
+#[test]
+fn stdin_cannot_be_formatted_in_place() {
+    let stderr = common::stderr(common::run(&["-", "--in-place"], ""));
+    assert!(stderr.contains("Cannot write in place when reading from stdin"), "{}", stderr);
+}
+
+#[test]
+fn any_reader_can_be_read_as_a_config() {
+    let contents = read_from(Cursor::new(CONFIG.as_bytes())).unwrap();
+    assert_eq!(contents, CONFIG);
+    assert!(format(&parse(&contents).unwrap()).starts_with("<OpenFile> TraderConfig.txt"));
+
+    let err = read_from(Cursor::new([0x3c, 0xff, 0xfe])).unwrap_err();
+    assert!(matches!(err, ParseError::Io { .. }), "{:?}", err);
+}
@@ -0,0 +1,46 @@
+use trader_config_formatter::{format, format_with, items, parse, parse_strict, FormatOptions, ParseError};
+
+const CONFIG: &str = "\
+<Trader> Boris
+    <Category> Weapons
+        AKM, *, 1000, 500
+        M4A1, *, 2000, 1000, 3 // limited
+<FileEnd>
+";
+
+#[test]
+fn four_and_five_value_items_are_parsed() {
+    let tokens = parse(CONFIG).unwrap();
+    let stocks: Vec<Option<&str>> = items(&tokens).map(|(_, _, i)| i.stock.as_deref()).collect();
+    assert_eq!(stocks, [None, Some("3")]);
+}
+
+#[test]
+fn stock_is_written_back() {
+    let formatted = format(&parse(CONFIG).unwrap());
+    let lines: Vec<&str> = formatted.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines[2], "AKM,                                                        *,        1000,     500");
+    assert!(lines[3].starts_with("M4A1,"));
+    assert!(lines[3].ends_with("1000,     3         // limited"));
+    assert_eq!(format(&parse(&formatted).unwrap()), formatted);
+
+    let compact = format_with(&parse(CONFIG).unwrap(), &FormatOptions { compact: true, ..FormatOptions::default() });
+    assert!(compact.contains("M4A1, *, 2000, 1000, 3 // limited"));
+}
+
+#[test]
+fn strict_parsing_only_allows_four_values() {
+    assert!(parse_strict("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n<FileEnd>\n").is_ok());
+    let err = parse_strict(CONFIG).unwrap_err();
+    assert!(matches!(err, ParseError::StockColumn { .. }), "{}", err);
+    assert!(err.to_string().contains("has a stock value, vanilla traders only read 4 values"), "{}", err);
+    assert!(!err.to_string().contains("extra comma"), "{}", err);
+}
+
+#[test]
+fn a_sixth_value_is_an_extra_comma() {
+    let err = parse("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500, 3, 1\n").unwrap_err();
+    assert!(matches!(err, ParseError::MissingCsvValues { found: 6, .. }), "{}", err);
+    assert!(err.to_string().contains("has 6 values instead of 4 or 5"), "{}", err);
+    assert!(err.to_string().contains("probably an extra comma"), "{}", err);
+}
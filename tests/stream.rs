@@ -0,0 +1,56 @@
+use trader_config_formatter::{
+    format, format_token, format_with, parse, parse_iter, write_formatted, FormatOptions, LineEnding, Token,
+};
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const MULTI_TRADER: &str = include_str!("fixtures/MultiTrader.txt");
+
+#[test]
+fn formatting_token_by_token_matches_the_whole_file() {
+    let opts = FormatOptions { preserve_blank_lines: true, ..FormatOptions::default() };
+    let streamed: String = parse_iter(CONFIG)
+        .map(|t| format_with(&[t.unwrap()], &opts))
+        .collect();
+    assert_eq!(streamed, format_with(&parse(CONFIG).unwrap(), &opts));
+
+    let tokens: Vec<Token> = parse_iter(CONFIG).collect::<Result<_, _>>().unwrap();
+    assert_eq!(format(&tokens), format(&parse(CONFIG).unwrap()));
+}
+
+#[test]
+fn formatting_token_by_token_keeps_the_spacing_between_blocks() {
+    let opts = FormatOptions::default();
+    let mut streamed = String::new();
+    let mut previous = None;
+    for t in parse_iter(MULTI_TRADER) {
+        let t = t.unwrap();
+        streamed.push_str(&format_token(&t, previous.as_ref(), &opts));
+        if !matches!(t, Token::BlankLine) {
+            previous = Some(t);
+        }
+    }
+    assert_eq!(streamed, format(&parse(MULTI_TRADER).unwrap()));
+}
+
+#[test]
+fn iteration_stops_after_an_error() {
+    let mut tokens = parse_iter("// first\n<Unknown> tag\n<FileEnd>\n");
+    assert!(matches!(tokens.next(), Some(Ok(Token::Comment(_)))));
+    assert!(matches!(tokens.next(), Some(Err(_))));
+    assert!(tokens.next().is_none());
+}
+
+#[test]
+fn writing_into_a_buffer_matches_the_formatted_string() {
+    for contents in [CONFIG, MULTI_TRADER] {
+        let tokens = parse(contents).unwrap();
+        for opts in [
+            FormatOptions::default(),
+            FormatOptions { preserve_blank_lines: true, line_ending: LineEnding::CrLf, ..FormatOptions::default() },
+        ] {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_formatted(&tokens, &mut buffer, &opts).unwrap();
+            assert_eq!(String::from_utf8(buffer).unwrap(), format_with(&tokens, &opts));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+
+use trader_config_formatter::{format, parse, parse_with, strip_comments, validate, ParseOptions};
+
+mod common;
+
+const CONFIG: &str = "\
+// Server trader config
+<OpenFile> TraderConfig.txt // main file
+<CurrencyName> Rubles // currency
+    // notes
+    <Currency> MoneyRuble100, 100 // big
+/* block comment */
+<Trader> Boris // main trader
+    // weapons first
+    <Category> Weapons /* WIP */
+        // rifles
+        AKM, *, 1000, 500 // popular
+        M4A1, *, 2000, 1000
+<FileEnd> // end
+";
+
+#[test]
+fn strips_every_comment_and_keeps_the_structure() {
+    let mut tokens = parse(CONFIG).unwrap();
+    strip_comments(&mut tokens);
+
+    assert_eq!(tokens.comments().count(), 0);
+    assert_eq!(tokens.traders().count(), 1);
+    assert_eq!(tokens.items().count(), 2);
+    assert!(validate(&tokens).is_empty());
+
+    let formatted = format(&tokens);
+    assert!(!formatted.contains("//") && !formatted.contains("/*"), "{}", formatted);
+    assert!(formatted.contains("<Category> Weapons\n"), "{}", formatted);
+    assert!(formatted.lines().all(|l| l == l.trim_end()), "{}", formatted);
+    assert_eq!(parse(&formatted).unwrap().items().count(), 2);
+}
+
+#[test]
+fn raw_headers_lose_their_comment_too() {
+    let opts = ParseOptions { raw_headers: true, ..ParseOptions::default() };
+    let mut tokens = parse_with("<OpenFile>   TraderConfig.txt // main\n<FileEnd>\n", &opts).unwrap();
+    strip_comments(&mut tokens);
+    assert_eq!(format(&tokens), "<OpenFile> TraderConfig.txt\n\n<FileEnd>\n");
+}
+
+#[test]
+fn strip_comments_flag_writes_a_comment_free_config() {
+    let dir = common::temp_dir("strip_comments");
+    let path = dir.join("TraderConfig.txt");
+    fs::write(&path, CONFIG).unwrap();
+
+    let output = common::formatter()
+        .args(["--strip-comments", "--validate"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("//") && !stdout.contains("/*"), "{}", stdout);
+    assert_eq!(parse(&stdout).unwrap().items().count(), 2);
+}
+
+#[test]
+fn block_comments_before_a_line_comment_on_a_tag_line_are_stripped() {
+    let config = "<Trader> Boris /* main */ // note\n    <Category> Weapons /* a */ /* b */ // c\n        AKM, *, 1000, 500\n";
+    let stdout = common::stdout(common::run(&["--strip-comments", "-"], config));
+    assert!(stdout.starts_with("<Trader> Boris\n    <Category> Weapons\n"), "{}", stdout);
+    assert!(!stdout.contains("//") && !stdout.contains("/*"), "{}", stdout);
+}
@@ -0,0 +1,85 @@
+use predicates::prelude::*;
+
+mod common;
+
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+const CSV: &str = include_str!("fixtures/TraderConfig.csv");
+
+#[test]
+fn format_subcommand_matches_the_bare_command() {
+    common::formatter().args(["format", "tests/fixtures/TraderConfig.txt"]).assert().success().stdout(FORMATTED);
+    common::formatter().arg("tests/fixtures/TraderConfig.txt").assert().success().stdout(FORMATTED);
+}
+
+#[test]
+fn validate_subcommand_only_prints_errors() {
+    common::formatter()
+        .args(["validate", "tests/fixtures/TraderConfig.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    common::formatter()
+        .args(["validate", "-"])
+        .write_stdin("<Trader> Boris\n    <Category> Weapons\n        AKM, *, abc, 500\n<FileEnd>\n")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("buy value \"abc\" is not a number"));
+}
+
+#[test]
+fn convert_subcommand_writes_json_or_csv() {
+    common::formatter()
+        .args(["convert", "--to", "csv", "tests/fixtures/TraderConfig.txt"])
+        .assert()
+        .success()
+        .stdout(CSV);
+    let output = common::formatter()
+        .args(["convert", "--to", "json", "tests/fixtures/TraderConfig.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json = String::from_utf8(output.stdout).unwrap();
+    common::formatter().args(["--from", "json", "-"]).write_stdin(json).assert().success().stdout(FORMATTED);
+}
+
+#[test]
+fn stats_subcommand_prints_counts() {
+    common::formatter()
+        .args(["stats", "tests/fixtures/TraderConfig.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Traders:           2\n"));
+}
+
+#[test]
+fn subcommands_reject_arguments_of_other_modes() {
+    common::formatter()
+        .args(["validate", "--in-place", "tests/fixtures/TraderConfig.txt"])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("--in-place can't be used with the validate subcommand"));
+    common::formatter().args(["convert", "tests/fixtures/TraderConfig.txt"]).assert().code(4);
+}
+
+#[test]
+fn output_that_is_not_a_config_cannot_replace_the_input() {
+    let dir = common::temp_dir("not_a_config");
+    let file = dir.join("TraderConfig.txt");
+    let original = std::fs::read_to_string("tests/fixtures/TraderConfig.txt").unwrap();
+    std::fs::write(&file, &original).unwrap();
+    let path = file.to_str().unwrap();
+    for args in [
+        vec!["--list-traders", path, "-o", path],
+        vec!["convert", "--to", "csv", path, "-o", path],
+        vec!["stats", path, "-o", path],
+    ] {
+        common::formatter()
+            .args(&args)
+            .assert()
+            .code(4)
+            .stderr(predicate::str::contains("Only a formatted config can be written over the input file"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,143 @@
+use predicates::prelude::*;
+
+use trader_config_formatter::{parse, profitable_resales, validate, validate_with, ValidateOptions};
+
+mod common;
+
+fn messages(config: &str) -> Vec<String> {
+    validate(&parse(config).unwrap()).into_iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn reports_a_class_listed_twice_in_a_category() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n        AKM, *, 900, 400\n";
+    assert_eq!(
+        messages(config),
+        ["Boris > Weapons > AKM: class is listed more than once in the category, first at 3:9 and again at 4:9"],
+    );
+}
+
+#[test]
+fn reports_a_class_listed_in_several_categories_of_a_trader() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, -1\n        M4A1, *, 2000, 1000\n\
+                  \x20   <Category> Sales\n        AKM, *, -1, 500\n    <Category> Food\n        Apple, *, 5, 1\n\
+                  \x20   <Category> Used\n        AKM, *, -1, 250\n";
+    assert_eq!(messages(config), ["Boris > AKM: class is listed in more than one category: Weapons, Sales, Used"]);
+}
+
+#[test]
+fn classes_in_several_categories_are_reported_in_the_order_they_first_appear() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        SVD, *, 3000, -1\n        AKM, *, 1000, -1\n\
+                  \x20   <Category> Sales\n        AKM, *, -1, 500\n        SVD, *, -1, 1500\n";
+    assert_eq!(
+        messages(config),
+        [
+            "Boris > SVD: class is listed in more than one category: Weapons, Sales",
+            "Boris > AKM: class is listed in more than one category: Weapons, Sales",
+        ]
+    );
+}
+
+#[test]
+fn classes_of_different_traders_are_not_compared() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 1000, 500\n\
+                  <Trader> Ivan\n    <Category> Weapons\n        AKM, *, 1000, 500\n";
+    assert!(messages(config).is_empty());
+}
+
+#[test]
+fn reports_a_currency_name_without_a_name() {
+    assert_eq!(messages("<CurrencyName>\n    <Currency> MoneyRuble100, 100\n"), ["<CurrencyName> has no name"]);
+}
+
+#[test]
+fn reports_currencies_without_a_positive_value() {
+    let config = "<CurrencyName> Rubles\n    <Currency> MoneyRuble100, 100\n    <Currency> MoneyRuble50, fifty\n\
+                  \x20   <Currency> MoneyRuble0, 0\n    <Currency> MoneyRuble10\n";
+    assert_eq!(messages(config), [
+        "MoneyRuble50: value \"fifty\" of <CurrencyName> Rubles is not a positive number",
+        "MoneyRuble0: value \"0\" of <CurrencyName> Rubles is not a positive number",
+        "MoneyRuble10: <Currency> of <CurrencyName> Rubles has 1 values instead of 2 (class, value)",
+    ]);
+}
+
+#[test]
+fn percentages_are_valid_buy_and_sell_values() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 25%, -1\n        M4A1, 1, 1000, 50%\n";
+    assert!(messages(config).is_empty(), "{:?}", messages(config));
+}
+
+#[test]
+fn reports_values_that_are_not_numbers_or_percentages() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, 5%, 2x5%, %\n";
+    assert_eq!(
+        messages(config),
+        [
+            "Boris > Weapons > AKM: amount \"5%\" is not a number or a quantity marker",
+            "Boris > Weapons > AKM: buy value \"2x5%\" is not a number, a percentage or -1",
+            "Boris > Weapons > AKM: sell value \"%\" is not a number, a percentage or -1",
+        ],
+    );
+}
+
+#[test]
+fn reports_a_letter_o_typed_for_a_zero() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 10O, 500\n";
+    assert_eq!(messages(config), ["Boris > Weapons > AKM: buy value \"10O\" is not a number, a percentage or -1"]);
+}
+
+#[test]
+fn minus_one_is_valid_for_either_price() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, -1, 500\n        M4A1, *, 1000, -1\n";
+    assert!(messages(config).is_empty(), "{:?}", messages(config));
+}
+
+#[test]
+fn a_file_that_fails_validation_exits_with_2() {
+    common::formatter()
+        .args(["--validate", "-"])
+        .write_stdin("<Trader> Boris\n    <Category> Weapons\n        AKM, *, 10O, -1\n<FileEnd>\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Boris > Weapons > AKM: buy value \"10O\""));
+}
+
+#[test]
+fn amounts_up_to_the_maximum_are_valid() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        \
+                  AKM, 0, 10, 5\n        M4A1, 1000000, 10, 5\n        SVD, -1, 10, 5\n";
+    assert!(messages(config).is_empty(), "{:?}", messages(config));
+}
+
+#[test]
+fn reports_amounts_past_the_maximum() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        \
+                  AKM, 1000001, 10, 5\n        M4A1, 99999999999999999999999, 10, 5\n";
+    assert_eq!(
+        messages(config),
+        [
+            "Boris > Weapons > AKM: amount 1000001 is more than the maximum of 1000000",
+            "Boris > Weapons > M4A1: amount 99999999999999999999999 is more than the maximum of 1000000",
+        ],
+    );
+}
+
+#[test]
+fn maximum_amount_can_be_changed() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, 51, 10, 5\n        M4A1, 50, 10, 5\n";
+    let tokens = parse(config).unwrap();
+    let violations = validate_with(&tokens, &ValidateOptions { max_amount: 50 });
+    let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(messages, ["Boris > Weapons > AKM: amount 51 is more than the maximum of 50"]);
+}
+
+#[test]
+fn economy_check_reports_items_that_sell_for_more_than_they_cost() {
+    let config = "<Trader> Boris\n    <Category> Weapons\n        AKM, *, 500, 1000\n        M4A1, *, 2000, 1000\n\
+                  \x20       SVD, *, -1, 5000\n        Mosin, *, 100, -1\n        VSS, *, 300, 300\n";
+    let messages: Vec<String> = profitable_resales(&parse(config).unwrap()).iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        messages,
+        ["Boris > Weapons > AKM: sells for 1000, more than it's bought for (500), so it can be resold for a profit"],
+    );
+}
@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use assert_cmd::cargo::CommandCargoExt;
+
+mod common;
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+const FORMATTED: &str = include_str!("fixtures/TraderConfig.formatted.txt");
+
+fn wait_for(file: &Path, contents: &str) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if fs::read_to_string(file).is_ok_and(|c| c == contents) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn watched_files_are_formatted_again_after_a_change() {
+    let dir = common::temp_dir("watch");
+    let file = dir.join("TraderConfig.txt");
+    fs::write(&file, CONFIG).unwrap();
+
+    // The watcher runs until it's killed, so it's spawned instead of asserted on
+    let mut child = Command::cargo_bin("trader_config_formatter")
+        .unwrap()
+        .args(["--watch", "--in-place"])
+        .arg(&file)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let first = wait_for(&file, FORMATTED);
+    // Give the watcher a moment to skip the event of its own write
+    thread::sleep(Duration::from_millis(500));
+    fs::write(&file, CONFIG).unwrap();
+    let second = wait_for(&file, FORMATTED);
+    thread::sleep(Duration::from_millis(500));
+
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(first && second, "file wasn't reformatted, stderr: {}", stderr);
+    assert_eq!(stderr.matches(": formatted\n").count(), 2, "{}", stderr);
+}
@@ -0,0 +1,29 @@
+use trader_config_formatter::{format_with, parse, CommentColumn, FormatOptions, Indent, LineEnding};
+
+const CONFIG: &str = include_str!("fixtures/TraderConfig.txt");
+
+fn options() -> Vec<FormatOptions> {
+    let default = FormatOptions::default();
+    vec![
+        default.clone(),
+        FormatOptions { preserve_blank_lines: true, ..default.clone() },
+        FormatOptions { compact: true, ..default.clone() },
+        FormatOptions { comment_column: CommentColumn::Widest, ..default.clone() },
+        FormatOptions { comment_column: CommentColumn::Fixed(20), ..default.clone() },
+        FormatOptions { indent: Indent::Tabs(1), ..default.clone() },
+        FormatOptions { indent: Indent::Spaces(2), line_ending: LineEnding::CrLf, ..default },
+    ]
+}
+
+#[test]
+fn no_line_ends_with_whitespace() {
+    let config = format!("{}<Trader>\n    <Category>\n        Apple, *, 5, 1\n<FileEnd>\n", CONFIG);
+    let tokens = parse(&config).unwrap();
+    for opts in options() {
+        let formatted = format_with(&tokens, &opts);
+        for (i, line) in formatted.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            assert_eq!(line, line.trim_end_matches([' ', '\t']), "line {} ends with whitespace with {:?}", i + 1, opts);
+        }
+    }
+}